@@ -0,0 +1,160 @@
+//! Incremental packet decoding, for use with non-blocking sockets.
+
+use error::{Error, Result};
+use limits;
+use packet::{Direction, PacketEnum, State, decode_packet, inflate_frame};
+
+/// Buffers bytes as they arrive and decodes complete packets from them.
+///
+/// A frame is only consumed once it has been received completely, so partial lengths and
+/// bodies are kept around until the next call to `feed`.
+pub struct Decoder {
+    buf: Vec<u8>,
+    /// The position in `buf` of the first byte which has not been decoded yet.
+    pos: usize,
+    /// The compression threshold, with the same meaning as for `Packet::write`.
+    ///
+    /// This must be updated as soon as a `SetCompression` packet has been decoded, since the
+    /// following frames may already be buffered.
+    pub compression: Option<usize>
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder {
+            buf: Vec::new(),
+            pos: 0,
+            compression: None
+        }
+    }
+
+    /// Appends bytes received from the network to the buffer.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        // Decoded frames are only dropped here, so that a burst of frames isn't copied once
+        // for each of them.
+        if self.pos > 0 {
+            self.buf = self.buf[self.pos..].to_vec();
+            self.pos = 0;
+        }
+        self.buf.extend(bytes.iter().cloned());
+    }
+
+    /// The number of buffered bytes which have not been decoded yet.
+    pub fn buffered(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    /// Decodes the next packet if its frame has been received completely, or returns `None`
+    /// if more bytes are needed.
+    ///
+    /// The frame is removed from the buffer even if decoding it fails.
+    pub fn next_packet(&mut self, direction: Direction, state: State) -> Result<Option<PacketEnum>> {
+        let (len, len_len) = match try!(frame_len(&self.buf[self.pos..])) {
+            Some(header) => header,
+            None => return Ok(None)
+        };
+        if self.buffered() < len_len + len {
+            return Ok(None);
+        }
+        let start = self.pos + len_len;
+        let frame = self.buf[start..start + len].to_vec();
+        self.pos = start + len;
+        let frame = try!(inflate_frame(frame, self.compression));
+        decode_packet(direction, state, &frame).map(Some)
+    }
+}
+
+/// Reads the VarInt frame length at the start of `buf`, returning it along with its own
+/// length in bytes, or `None` if it has not been received completely.
+fn frame_len(buf: &[u8]) -> Result<Option<(usize, usize)>> {
+    let mut value = 0i32;
+    for (i, &byte) in buf.iter().take(5).enumerate() {
+        value |= ((byte & 0x7f) as i32) << (7 * i);
+        if (byte & 0x80) == 0 {
+            if value < 0 {
+                return Err(Error::invalid("invalid frame length", Some(format!("frame length must not be negative, found {}", value))));
+            }
+            // Checked before the frame arrives, so that it is never buffered.
+            try!(limits::check("frame size", value as usize, limits::current().max_frame_size));
+            return Ok(Some((value as usize, i + 1)));
+        }
+    }
+    if buf.len() >= 5 {
+        Err(Error::invalid("VarInt too big", None))
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::default::Default;
+
+    use error::ErrorKind;
+    use limits::{self, Limits};
+    use packet::{Direction, PacketEnum, State};
+    use packet::status;
+
+    fn is_pong(packet: Option<PacketEnum>, time: i64) -> bool {
+        match packet {
+            Some(PacketEnum::Status(status::PacketEnum::Clientbound(status::clientbound::PacketEnum::Pong(pong)))) => pong.time == time,
+            _ => false
+        }
+    }
+
+    #[test]
+    fn decoder_partial_frames() {
+        let bytes = vec![0x09, 0x01, 0, 0, 0, 0, 0, 0, 0, 1];
+        let mut decoder = Decoder::new();
+        for &byte in &bytes[..bytes.len() - 1] {
+            decoder.feed(&[byte]);
+            assert!(decoder.next_packet(Direction::Clientbound, State::Status).unwrap().is_none());
+        }
+        decoder.feed(&bytes[bytes.len() - 1..]);
+        assert!(is_pong(decoder.next_packet(Direction::Clientbound, State::Status).unwrap(), 1));
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[test]
+    fn decoder_multiple_frames() {
+        let bytes = vec![
+            0x09, 0x01, 0, 0, 0, 0, 0, 0, 0, 1,
+            0x09, 0x01, 0, 0, 0, 0, 0, 0, 0, 2,
+            0x09
+        ];
+        let mut decoder = Decoder::new();
+        decoder.feed(&bytes);
+        assert!(is_pong(decoder.next_packet(Direction::Clientbound, State::Status).unwrap(), 1));
+        assert!(is_pong(decoder.next_packet(Direction::Clientbound, State::Status).unwrap(), 2));
+        assert!(decoder.next_packet(Direction::Clientbound, State::Status).unwrap().is_none());
+        assert_eq!(decoder.buffered(), 1);
+        decoder.feed(&[0x01, 0, 0, 0, 0, 0, 0, 0, 3]);
+        assert!(is_pong(decoder.next_packet(Direction::Clientbound, State::Status).unwrap(), 3));
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[test]
+    fn decoder_compressed_frame() {
+        let bytes = vec![0x0a, 0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 1];
+        let mut decoder = Decoder::new();
+        decoder.compression = Some(256);
+        decoder.feed(&bytes);
+        assert!(is_pong(decoder.next_packet(Direction::Clientbound, State::Status).unwrap(), 1));
+    }
+
+    #[test]
+    fn decoder_frame_limit() {
+        let _limits = limits::scope(Limits { max_frame_size: 8, ..Default::default() });
+        let mut decoder = Decoder::new();
+        decoder.feed(&[0x09, 0x01]);
+        match decoder.next_packet(Direction::Clientbound, State::Status) {
+            Err(err) => match err.kind {
+                ErrorKind::LimitExceeded { limit: "frame size", max: 8 } => {}
+                kind => panic!("unexpected error kind {:?}", kind)
+            },
+            Ok(_) => panic!("accepted a frame over the limit")
+        }
+    }
+}
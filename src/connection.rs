@@ -0,0 +1,220 @@
+//! Connections which keep track of the protocol state.
+
+use std::any::Any;
+use std::io::prelude::*;
+
+use error::{Error, Result};
+use packet::{Direction, Packet, PacketEnum, State};
+use packet::{handshake, login, play};
+use version::Version;
+
+/// A stream of packets which follows the state changes of the protocol.
+///
+/// Every connection starts in `Handshaking` and only leaves it through the packets which
+/// change the state in vanilla: `Handshake` moves to `Status` or `Login`, and `LoginSuccess`
+/// moves to `Play`. `SetCompression` also updates the compression threshold.
+///
+/// The protocol version is taken from the `Handshake` packet, and packets are translated to
+/// and from that version's IDs and layouts. Status requests from unsupported versions are
+/// answered with protocol 47, but logging in with them fails.
+pub struct Connection<S> {
+    stream: S,
+    state: State,
+    direction: Direction,
+    version: Version,
+    compression: Option<usize>
+}
+
+impl<S: Read + Write> Connection<S> {
+    /// Creates a connection in the `Handshaking` state.
+    ///
+    /// `direction` is the direction of the packets read from `stream`, i.e. `Serverbound` on
+    /// the server side and `Clientbound` on the client side.
+    pub fn new(stream: S, direction: Direction) -> Connection<S> {
+        Connection {
+            stream: stream,
+            state: State::Handshaking,
+            direction: direction,
+            version: Version::V47,
+            compression: None
+        }
+    }
+
+    /// The current state of the connection.
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// The direction of the packets read from the connection.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// The protocol version used by the connection.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// The compression threshold, with the same meaning as for `Packet::write`.
+    pub fn compression(&self) -> Option<usize> {
+        self.compression
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    /// Reads the next packet, decoding it for the current state and updating the state if
+    /// needed.
+    pub fn read_packet(&mut self) -> Result<PacketEnum> {
+        let packet = try!(self.version.read_packet(self.direction, self.state, self.compression, &mut self.stream));
+        match packet {
+            PacketEnum::Handshaking(handshake::PacketEnum::Serverbound(handshake::serverbound::PacketEnum::Handshake(ref p))) => try!(self.update(p)),
+            PacketEnum::Login(login::PacketEnum::Clientbound(login::clientbound::PacketEnum::LoginSuccess(ref p))) => try!(self.update(p)),
+            PacketEnum::Login(login::PacketEnum::Clientbound(login::clientbound::PacketEnum::SetCompression(ref p))) => try!(self.update(p)),
+            PacketEnum::Play(play::PacketEnum::Clientbound(play::clientbound::PacketEnum::SetCompression(ref p))) => try!(self.update(p)),
+            _ => {}
+        }
+        Ok(packet)
+    }
+
+    /// Writes a packet, failing without writing anything if it does not belong to the current
+    /// state or is sent in the same direction as the packets read from the connection.
+    pub fn write_packet<P: Packet + Any>(&mut self, packet: &P) -> Result<()> {
+        if packet.state() != self.state {
+            return Err(Error::invalid("packet sent in the wrong state", Some(format!("packet with id {:#x} belongs to {:?}, but the connection is in {:?}", packet.id(), packet.state(), self.state))));
+        }
+        if packet.direction() == self.direction {
+            return Err(Error::invalid("packet sent in the wrong direction", Some(format!("{:?} packets can only be read from this connection", self.direction))));
+        }
+        try!(self.version.write_packet(packet, &mut self.stream, self.compression));
+        self.update(packet)
+    }
+
+    /// Applies the state changes caused by a packet which has just been read or written.
+    fn update(&mut self, packet: &Any) -> Result<()> {
+        if let Some(p) = packet.downcast_ref::<handshake::serverbound::Handshake>() {
+            match Version::from_protocol(p.proto_version) {
+                Some(version) => self.version = version,
+                None if p.next_state == State::Login => {
+                    return Err(Error::invalid("unsupported protocol version", Some(format!("can't log in with protocol {}", p.proto_version))));
+                }
+                None => {}
+            }
+            // The `State` decoder only accepts `Status` and `Login` here.
+            self.state = p.next_state;
+        } else if packet.downcast_ref::<login::clientbound::LoginSuccess>().is_some() {
+            self.state = State::Play;
+        } else if let Some(p) = packet.downcast_ref::<login::clientbound::SetCompression>() {
+            self.compression = threshold(p.threshold);
+        } else if let Some(p) = packet.downcast_ref::<play::clientbound::SetCompression>() {
+            self.compression = threshold(p.threshold);
+        }
+        Ok(())
+    }
+}
+
+/// Converts the threshold sent in `SetCompression`, where a negative value disables compression.
+fn threshold(value: i32) -> Option<usize> {
+    if value < 0 { None } else { Some(value as usize) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use connection::Connection;
+    use packet::{Direction, Packet, PacketEnum, State};
+    use packet::handshake::serverbound::Handshake;
+    use packet::login::clientbound::{LoginSuccess, SetCompression};
+    use packet::login::serverbound::LoginStart;
+    use packet::play::clientbound::KeepAlive;
+    use packet::play::serverbound::ChatMessage;
+    use uuid::Uuid;
+    use version::Version;
+
+    fn handshake(next_state: State) -> Handshake {
+        Handshake {
+            proto_version: 47,
+            server_address: "localhost".to_string(),
+            server_port: 25565,
+            next_state: next_state
+        }
+    }
+
+    #[test]
+    fn server_login_to_play() {
+        let mut src = Vec::new();
+        handshake(State::Login).write(&mut src, None).unwrap();
+        LoginStart { name: "Herobrine".to_string() }.write(&mut src, None).unwrap();
+        let mut conn = Connection::new(io::Cursor::new(src), Direction::Serverbound);
+
+        match conn.read_packet().unwrap() {
+            PacketEnum::Handshaking(_) => {}
+            _ => panic!("expected a handshake packet")
+        }
+        assert_eq!(conn.state(), State::Login);
+        match conn.read_packet().unwrap() {
+            PacketEnum::Login(_) => {}
+            _ => panic!("expected a login packet")
+        }
+
+        conn.write_packet(&SetCompression { threshold: 256 }).unwrap();
+        assert_eq!(conn.compression(), Some(256));
+        let uuid = Uuid::from_bytes(&[0x66; 16]).unwrap();
+        conn.write_packet(&LoginSuccess { uuid: uuid, username: "Herobrine".to_string() }).unwrap();
+        assert_eq!(conn.state(), State::Play);
+        conn.write_packet(&KeepAlive { keep_alive_id: 1 }).unwrap();
+    }
+
+    #[test]
+    fn out_of_state_packets() {
+        let mut conn = Connection::new(io::Cursor::new(Vec::new()), Direction::Serverbound);
+        assert!(conn.write_packet(&KeepAlive { keep_alive_id: 1 }).is_err());
+        assert!(conn.write_packet(&handshake(State::Login)).is_err());
+        assert!(conn.get_ref().get_ref().is_empty());
+        assert_eq!(conn.state(), State::Handshaking);
+    }
+
+    #[test]
+    fn read_out_of_state_packet() {
+        let mut src = Vec::new();
+        ChatMessage { message: "hi".to_string() }.write(&mut src, None).unwrap();
+        let mut conn = Connection::new(io::Cursor::new(src), Direction::Serverbound);
+        assert!(conn.read_packet().is_err());
+    }
+
+    #[test]
+    fn version_from_handshake() {
+        let mut src = Vec::new();
+        Handshake { proto_version: 5, ..handshake(State::Login) }.write(&mut src, None).unwrap();
+        let mut conn = Connection::new(io::Cursor::new(src), Direction::Serverbound);
+        conn.read_packet().unwrap();
+        assert_eq!(conn.version(), Version::V5);
+        assert!(conn.write_packet(&SetCompression { threshold: 256 }).is_err());
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut src = Vec::new();
+        Handshake { proto_version: 4, ..handshake(State::Status) }.write(&mut src, None).unwrap();
+        let mut conn = Connection::new(io::Cursor::new(src), Direction::Serverbound);
+        conn.read_packet().unwrap();
+        assert_eq!(conn.state(), State::Status);
+        assert_eq!(conn.version(), Version::V47);
+
+        let mut src = Vec::new();
+        Handshake { proto_version: 4, ..handshake(State::Login) }.write(&mut src, None).unwrap();
+        let mut conn = Connection::new(io::Cursor::new(src), Direction::Serverbound);
+        assert!(conn.read_packet().is_err());
+        assert_eq!(conn.state(), State::Handshaking);
+    }
+}
@@ -0,0 +1,180 @@
+//! AES-128/CFB8 stream encryption, enabled after the login handshake.
+
+use std::io;
+use std::io::prelude::*;
+
+use crypto::aessafe::AesSafe128Encryptor;
+use crypto::symmetriccipher::BlockEncryptor;
+
+/// The AES-128 cipher in 8-bit cipher feedback mode.
+struct Cfb8 {
+    aes: AesSafe128Encryptor,
+    iv: [u8; 16]
+}
+
+impl Cfb8 {
+    fn new(key: &[u8], iv: &[u8]) -> io::Result<Cfb8> {
+        if key.len() != 16 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid shared secret", Some(format!("expected a 16 byte key, found {} bytes", key.len()))));
+        }
+        if iv.len() != 16 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid IV", Some(format!("expected a 16 byte IV, found {} bytes", iv.len()))));
+        }
+        let mut cfb = Cfb8 {
+            aes: AesSafe128Encryptor::new(key),
+            iv: [0u8; 16]
+        };
+        for (dst, src) in cfb.iv.iter_mut().zip(iv.iter()) {
+            *dst = *src;
+        }
+        Ok(cfb)
+    }
+
+    /// Returns the first byte of the encrypted feedback register.
+    fn keystream(&self) -> u8 {
+        let mut out = [0u8; 16];
+        self.aes.encrypt_block(&self.iv, &mut out);
+        out[0]
+    }
+
+    /// Shifts a ciphertext byte into the feedback register.
+    fn feedback(&mut self, byte: u8) {
+        for i in 0..15 {
+            self.iv[i] = self.iv[i + 1];
+        }
+        self.iv[15] = byte;
+    }
+
+    fn encrypt(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            *byte ^= self.keystream();
+            self.feedback(*byte);
+        }
+    }
+
+    fn decrypt(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            let cipher_byte = *byte;
+            *byte ^= self.keystream();
+            self.feedback(cipher_byte);
+        }
+    }
+}
+
+/// Decrypts everything read from the inner reader.
+///
+/// Minecraft uses the shared secret from `EncryptionResponse` as both the key and the IV.
+pub struct EncryptedReader<R> {
+    inner: R,
+    cipher: Cfb8
+}
+
+impl<R: Read> EncryptedReader<R> {
+    /// Wraps a reader, failing if the shared secret is not 16 bytes long.
+    pub fn new(inner: R, shared_secret: &[u8]) -> io::Result<EncryptedReader<R>> {
+        Ok(EncryptedReader {
+            inner: inner,
+            cipher: try!(Cfb8::new(shared_secret, shared_secret))
+        })
+    }
+
+    pub fn get_ref(&self) -> &R { &self.inner }
+
+    pub fn get_mut(&mut self) -> &mut R { &mut self.inner }
+
+    pub fn into_inner(self) -> R { self.inner }
+}
+
+impl<R: Read> Read for EncryptedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = try!(self.inner.read(buf));
+        self.cipher.decrypt(&mut buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Encrypts everything written to the inner writer.
+///
+/// Minecraft uses the shared secret from `EncryptionResponse` as both the key and the IV.
+pub struct EncryptedWriter<W> {
+    inner: W,
+    cipher: Cfb8
+}
+
+impl<W: Write> EncryptedWriter<W> {
+    /// Wraps a writer, failing if the shared secret is not 16 bytes long.
+    pub fn new(inner: W, shared_secret: &[u8]) -> io::Result<EncryptedWriter<W>> {
+        Ok(EncryptedWriter {
+            inner: inner,
+            cipher: try!(Cfb8::new(shared_secret, shared_secret))
+        })
+    }
+
+    pub fn get_ref(&self) -> &W { &self.inner }
+
+    pub fn get_mut(&mut self) -> &mut W { &mut self.inner }
+
+    pub fn into_inner(self) -> W { self.inner }
+}
+
+impl<W: Write> Write for EncryptedWriter<W> {
+    /// Encrypts and writes the whole buffer, since the cipher state can't be rolled back after
+    /// a partial write.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut data = buf.to_vec();
+        self.cipher.encrypt(&mut data);
+        try!(self.inner.write_all(&data));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::Cfb8;
+
+    use std::io;
+    use std::io::prelude::*;
+
+    #[test]
+    fn cfb8_nist_vector() {
+        // NIST SP 800-38A, F.3.7 CFB8-AES128.Encrypt
+        let key = [0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c];
+        let iv = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f];
+        let plain = vec![0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a, 0xae, 0x2d];
+        let cipher = vec![0x3b, 0x79, 0x42, 0x4c, 0x9c, 0x0d, 0xd4, 0x36, 0xba, 0xce, 0x9e, 0x0e, 0xd4, 0x58, 0x6a, 0x4f, 0x32, 0xb9];
+
+        let mut data = plain.clone();
+        Cfb8::new(&key, &iv).unwrap().encrypt(&mut data);
+        assert_eq!(&data, &cipher);
+        Cfb8::new(&key, &iv).unwrap().decrypt(&mut data);
+        assert_eq!(&data, &plain);
+    }
+
+    #[test]
+    fn encrypted_stream_roundtrip() {
+        let secret = b"0123456789abcdef";
+        let bytes = vec![0x1a, 0xc6, 0x45, 0x95, 0xe4, 0x2e, 0x03, 0xfe];
+
+        let mut w = EncryptedWriter::new(Vec::new(), secret).unwrap();
+        w.write_all(b"hema").unwrap();
+        w.write_all(b"tite").unwrap();
+        assert_eq!(w.get_ref(), &bytes);
+
+        let mut r = EncryptedReader::new(io::Cursor::new(bytes), secret).unwrap();
+        let mut buf = [0u8; 8];
+        assert_eq!(r.read(&mut buf).unwrap(), 8);
+        assert_eq!(&buf, b"hematite");
+    }
+
+    #[test]
+    fn encrypted_bad_secret() {
+        assert!(EncryptedReader::new(io::empty(), &[0u8; 15]).is_err());
+        assert!(Cfb8::new(&[0u8; 16], &[0u8; 8]).is_err());
+        assert!(Cfb8::new(&[0u8; 8], &[0u8; 16]).is_err());
+    }
+}
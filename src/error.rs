@@ -0,0 +1,181 @@
+//! Errors produced while encoding or decoding packets.
+
+use byteorder;
+
+use std::error::{self, FromError};
+use std::fmt;
+use std::io;
+use std::result;
+
+use packet::{Direction, State};
+
+pub type Result<T> = result::Result<T, Error>;
+
+/// What went wrong while encoding or decoding a value.
+#[derive(Debug)]
+pub enum ErrorKind {
+    /// No packet with this ID exists in the given state and direction.
+    UnknownPacketId { state: State, direction: Direction, id: i32 },
+    /// A number which doesn't correspond to any variant of the named enum.
+    InvalidEnumValue { name: &'static str, value: i64 },
+    /// A string longer than the protocol allows, in characters.
+    StringTooLong { len: usize, max: usize },
+    /// A length or size above the decode limit for it, see `limits::Limits`.
+    LimitExceeded { limit: &'static str, max: usize },
+    /// A string which is not valid UTF-8.
+    BadUtf8,
+    /// A malformed NBT tag.
+    Nbt(io::Error),
+    /// The data ended in the middle of a value.
+    UnexpectedEof,
+    /// Any other malformed value, with a short description and optional details.
+    InvalidValue(&'static str, Option<String>),
+    /// An error from the underlying reader or writer.
+    Io(io::Error)
+}
+
+/// A protocol error, along with the packet and field in which it happened.
+#[derive(Debug)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// The name of the packet being encoded or decoded, if known.
+    pub packet: Option<&'static str>,
+    /// The names of the fields leading to the value which failed, outermost first.
+    pub path: Vec<&'static str>
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind) -> Error {
+        Error {
+            kind: kind,
+            packet: None,
+            path: Vec::new()
+        }
+    }
+
+    /// Creates an `ErrorKind::InvalidValue` error.
+    pub fn invalid(desc: &'static str, detail: Option<String>) -> Error {
+        Error::new(ErrorKind::InvalidValue(desc, detail))
+    }
+
+    /// Adds the name of the field containing the failed value to the front of the path.
+    pub fn in_field(mut self, field: &'static str) -> Error {
+        self.path.insert(0, field);
+        self
+    }
+
+    /// Records the name of the packet containing the failed value, unless one is already set.
+    pub fn in_packet(mut self, packet: &'static str) -> Error {
+        if self.packet.is_none() {
+            self.packet = Some(packet);
+        }
+        self
+    }
+
+    /// The packet name and field path joined with dots, e.g. `ClickWindow.clicked_item.tag`.
+    pub fn location(&self) -> String {
+        let mut location = self.packet.unwrap_or("").to_string();
+        for field in self.path.iter() {
+            if !location.is_empty() {
+                location.push('.');
+            }
+            location.push_str(field);
+        }
+        location
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorKind::UnknownPacketId { ref state, ref direction, id } => write!(f, "no {:?} packet with id {:#x} in state {:?}", direction, id, state),
+            ErrorKind::InvalidEnumValue { name, value } => write!(f, "invalid {} value {}", name, value),
+            ErrorKind::StringTooLong { len, max } => write!(f, "string of {} characters is longer than {}", len, max),
+            ErrorKind::LimitExceeded { limit, max } => write!(f, "{} exceeds the limit of {}", limit, max),
+            ErrorKind::BadUtf8 => write!(f, "string is not UTF-8"),
+            ErrorKind::Nbt(ref err) => write!(f, "invalid NBT: {}", err),
+            ErrorKind::UnexpectedEof => write!(f, "unexpected EOF"),
+            ErrorKind::InvalidValue(desc, Some(ref detail)) => write!(f, "{}: {}", desc, detail),
+            ErrorKind::InvalidValue(desc, None) => write!(f, "{}", desc),
+            ErrorKind::Io(ref err) => write!(f, "{}", err)
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let location = self.location();
+        if location.is_empty() {
+            write!(f, "{}", self.kind)
+        } else {
+            write!(f, "{}: {}", location, self.kind)
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match self.kind {
+            ErrorKind::UnknownPacketId { .. } => "unknown packet id",
+            ErrorKind::InvalidEnumValue { .. } => "invalid enum value",
+            ErrorKind::StringTooLong { .. } => "string too long",
+            ErrorKind::LimitExceeded { .. } => "decode limit exceeded",
+            ErrorKind::BadUtf8 => "string is not UTF-8",
+            ErrorKind::Nbt(_) => "invalid NBT",
+            ErrorKind::UnexpectedEof => "unexpected EOF",
+            ErrorKind::InvalidValue(desc, _) => desc,
+            ErrorKind::Io(ref err) => err.description()
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match self.kind {
+            ErrorKind::Nbt(ref err) | ErrorKind::Io(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl FromError<io::Error> for Error {
+    fn from_error(err: io::Error) -> Error {
+        Error::new(ErrorKind::Io(err))
+    }
+}
+
+impl FromError<byteorder::Error> for Error {
+    fn from_error(err: byteorder::Error) -> Error {
+        match err {
+            byteorder::Error::UnexpectedEOF => Error::new(ErrorKind::UnexpectedEof),
+            byteorder::Error::Io(err) => Error::new(ErrorKind::Io(err))
+        }
+    }
+}
+
+/// Converts protocol errors for APIs which deal in `io::Error`, such as `NbtBlob`.
+impl FromError<Error> for io::Error {
+    fn from_error(err: Error) -> io::Error {
+        match err {
+            Error { kind: ErrorKind::Io(err), .. } | Error { kind: ErrorKind::Nbt(err), .. } => err,
+            err => io::Error::new(io::ErrorKind::InvalidInput, "protocol error", Some(err.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use error::{Error, ErrorKind};
+
+    #[test]
+    fn error_location() {
+        let err = Error::new(ErrorKind::UnexpectedEof).in_field("tag").in_field("clicked_item").in_packet("ClickWindow");
+        assert_eq!(err.location(), "ClickWindow.clicked_item.tag");
+        assert_eq!(err.to_string(), "ClickWindow.clicked_item.tag: unexpected EOF");
+    }
+
+    #[test]
+    fn error_without_location() {
+        let err = Error::invalid("VarInt too big", None);
+        assert_eq!(err.location(), "");
+        assert_eq!(err.to_string(), "VarInt too big");
+    }
+}
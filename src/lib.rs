@@ -3,9 +3,17 @@
 #![feature(rustc_private)]
 
 extern crate byteorder;
+extern crate crypto;
 extern crate flate2;
+extern crate "rustc-serialize" as rustc_serialize;
 extern crate uuid;
 
+pub mod codec;
+pub mod connection;
+pub mod encryption;
+pub mod error;
+pub mod limits;
 pub mod packet;
 pub mod types;
+pub mod version;
 mod util;
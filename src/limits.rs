@@ -0,0 +1,217 @@
+//! Limits on the size of decoded values, to protect against hostile input.
+//!
+//! Limits are kept per thread. Each thread starts out with `LimitConfig::default()`, which
+//! matches the vanilla server, and can install a `LimitConfig` with different defaults and
+//! overrides for single packets or packet fields.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::default::Default;
+
+use error::{Error, ErrorKind, Result};
+
+/// The largest values accepted while decoding.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    /// The maximum length of a string, in characters.
+    pub max_string_len: usize,
+    /// The maximum number of elements in a length-prefixed array.
+    pub max_array_len: usize,
+    /// The maximum nesting depth of NBT lists and compounds.
+    pub max_nbt_depth: usize,
+    /// The maximum size of an NBT blob, in bytes.
+    pub max_nbt_size: usize,
+    /// The maximum nesting depth of arrays and objects in chat JSON.
+    pub max_chat_depth: usize,
+    /// The maximum size of a frame, in bytes.
+    pub max_frame_size: usize,
+    /// The maximum size of a compressed packet once decompressed, in bytes.
+    pub max_packet_size: usize
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_string_len: 32767,
+            max_array_len: 2097152,
+            max_nbt_depth: 512,
+            max_nbt_size: 2097152,
+            max_chat_depth: 512,
+            // The largest length which fits in a 3 byte VarInt.
+            max_frame_size: 2097151,
+            max_packet_size: 2097152
+        }
+    }
+}
+
+/// Default limits along with overrides for single packets or fields.
+#[derive(Clone, Debug)]
+pub struct LimitConfig {
+    pub default: Limits,
+    packets: HashMap<String, Limits>,
+    fields: HashMap<String, HashMap<String, Limits>>
+}
+
+impl LimitConfig {
+    /// Creates a configuration using `default` everywhere, without any overrides.
+    pub fn new(default: Limits) -> LimitConfig {
+        LimitConfig {
+            default: default,
+            packets: HashMap::new(),
+            fields: HashMap::new()
+        }
+    }
+
+    /// Sets the limits used for a packet, given by its name (e.g. `"PluginMessage"`), or for
+    /// one of its fields (e.g. `"PluginMessage.channel"`). Packets are only identified by their
+    /// name, so the limits apply to both directions if they share it.
+    ///
+    /// Field limits take precedence over packet limits, which take precedence over the defaults.
+    pub fn set(&mut self, key: &str, limits: Limits) {
+        match key.find('.') {
+            Some(dot) => {
+                let (packet, field) = (&key[..dot], &key[dot + 1..]);
+                if !self.fields.contains_key(packet) {
+                    self.fields.insert(packet.to_string(), HashMap::new());
+                }
+                self.fields.get_mut(packet).unwrap().insert(field.to_string(), limits);
+            }
+            None => {
+                self.packets.insert(key.to_string(), limits);
+            }
+        }
+    }
+
+    /// The limits used while decoding the given packet.
+    pub fn packet(&self, packet: &str) -> Limits {
+        self.packets.get(packet).map(|&limits| limits).unwrap_or(self.default)
+    }
+
+    /// The limits set for a field of the given packet, if there are any.
+    pub fn field(&self, packet: &str, field: &str) -> Option<Limits> {
+        self.fields.get(packet).and_then(|fields| fields.get(field)).map(|&limits| limits)
+    }
+}
+
+/// The default limits, along with the shorter string lengths which the vanilla server accepts
+/// in some of the fields it reads.
+impl Default for LimitConfig {
+    fn default() -> LimitConfig {
+        let mut config = LimitConfig::new(Default::default());
+        for &(key, max_string_len) in [
+            ("Handshake.server_address", 255),
+            ("LoginStart.name", 16),
+            ("ChatMessage.message", 100),
+            ("ClientSettings.locale", 7),
+            ("PluginMessage.channel", 20),
+            ("ResourcePackStatus.hash", 40),
+            ("UpdateSign.line0", 384),
+            ("UpdateSign.line1", 384),
+            ("UpdateSign.line2", 384),
+            ("UpdateSign.line3", 384)
+        ].iter() {
+            config.set(key, Limits { max_string_len: max_string_len, ..Default::default() });
+        }
+        config
+    }
+}
+
+thread_local!(static CONFIG: RefCell<LimitConfig> = RefCell::new(Default::default()));
+thread_local!(static CURRENT: Cell<Limits> = Cell::new(Default::default()));
+
+/// Installs `config` for the current thread.
+pub fn configure(config: LimitConfig) {
+    CURRENT.with(|current| current.set(config.default));
+    CONFIG.with(|cell| *cell.borrow_mut() = config);
+}
+
+/// The limits in effect on the current thread.
+pub fn current() -> Limits {
+    CURRENT.with(|current| current.get())
+}
+
+/// Restores the previous limits when dropped.
+pub struct Scope {
+    previous: Limits
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let previous = self.previous;
+        CURRENT.with(|current| current.set(previous));
+    }
+}
+
+/// Uses `limits` on the current thread until the returned `Scope` is dropped.
+pub fn scope(limits: Limits) -> Scope {
+    let previous = current();
+    CURRENT.with(|current| current.set(limits));
+    Scope { previous: previous }
+}
+
+/// Uses the limits configured for a packet until the returned `Scope` is dropped.
+pub fn packet_scope(packet: &str) -> Scope {
+    scope(CONFIG.with(|config| config.borrow().packet(packet)))
+}
+
+/// Uses the limits configured for a packet field, if any, until the returned `Scope` is dropped.
+pub fn field_scope(packet: &str, field: &str) -> Scope {
+    let limits = CONFIG.with(|config| config.borrow().field(packet, field));
+    scope(limits.unwrap_or(current()))
+}
+
+/// Fails with `ErrorKind::LimitExceeded` if `value` is larger than `max`.
+pub fn check(limit: &'static str, value: usize, max: usize) -> Result<()> {
+    if value > max {
+        Err(Error::new(ErrorKind::LimitExceeded { limit: limit, max: max }))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::default::Default;
+
+    use limits::{self, LimitConfig, Limits};
+
+    #[test]
+    fn scopes_restore_limits() {
+        let small = Limits { max_string_len: 4, ..Default::default() };
+        {
+            let _scope = limits::scope(small);
+            assert_eq!(limits::current().max_string_len, 4);
+        }
+        assert_eq!(limits::current(), Limits::default());
+    }
+
+    #[test]
+    fn packet_and_field_overrides() {
+        let mut config = LimitConfig::new(Default::default());
+        config.set("PluginMessage", Limits { max_array_len: 10, ..Default::default() });
+        config.set("PluginMessage.channel", Limits { max_string_len: 20, ..Default::default() });
+        limits::configure(config);
+        {
+            let _packet = limits::packet_scope("PluginMessage");
+            assert_eq!(limits::current().max_array_len, 10);
+            {
+                let _field = limits::field_scope("PluginMessage", "channel");
+                assert_eq!(limits::current().max_string_len, 20);
+            }
+            let _field = limits::field_scope("PluginMessage", "data");
+            assert_eq!(limits::current().max_array_len, 10);
+        }
+        assert_eq!(limits::current(), Limits::default());
+        limits::configure(Default::default());
+    }
+
+    #[test]
+    fn vanilla_field_limits() {
+        let config: LimitConfig = Default::default();
+        assert_eq!(config.field("LoginStart", "name").unwrap().max_string_len, 16);
+        assert_eq!(config.field("ChatMessage", "message").unwrap().max_string_len, 100);
+        assert_eq!(config.field("ChatMessage", "position"), None);
+        assert_eq!(config.field("UpdateSign", "line3").unwrap().max_string_len, 384);
+        assert_eq!(config.packet("ChatMessage"), Limits::default());
+    }
+}
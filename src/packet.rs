@@ -2,33 +2,48 @@
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
 use std::error::FromError;
 use std::io;
 use std::io::prelude::*;
 
-use types::Var;
+use error::{Error, ErrorKind, Result};
+use limits;
+use types::consts::ModifierOperation;
+use types::{Arr, Chat, Var};
+use util::ReadExactExt;
+use uuid::Uuid;
 
 /// A trait used for data which can be encoded/decoded as is.
 pub trait Protocol {
     type Clean = Self;
 
     fn proto_len(value: &Self::Clean) -> usize;
-    fn proto_encode(value: &Self::Clean, dst: &mut Write) -> io::Result<()>;
-    fn proto_decode(src: &mut Read) -> io::Result<Self::Clean>;
+    fn proto_encode(value: &Self::Clean, dst: &mut Write) -> Result<()>;
+    fn proto_decode(src: &mut Read) -> Result<Self::Clean>;
 }
 
 /// Holds packet methods implemented by the `packets!` macro for all packets.
-trait PacketBase {
+pub trait PacketBase {
     /// The packet ID.
     fn id(&self) -> i32;
+    /// The name of the packet struct, used in errors.
+    fn name(&self) -> &'static str;
+    /// The connection state in which the packet is sent.
+    fn state(&self) -> State;
+    /// The direction in which the packet is sent.
+    fn direction(&self) -> Direction;
 }
 
 /// A trait for encoding/decoding the body of a single packet type.
-trait Packet: PacketBase {
+pub trait Packet: PacketBase {
     /// Encodes the packet body and writes it to a writer.
-    fn encode(&self, dst: &mut Write) -> io::Result<()>;
+    fn encode(&self, dst: &mut Write) -> Result<()>;
     /// Decodes the packet body from a reader.
-    fn decode(src: &mut Read, len: usize) -> io::Result<Self>;
+    fn decode(src: &mut Read, len: usize) -> Result<Self>;
 
     /// The length of the packet's fields, in bytes.
     ///
@@ -41,24 +56,178 @@ trait Packet: PacketBase {
 
     /// Writes a full packet to a writer, including length and packet ID.
     ///
-    /// **TODO:** add support for compression.
-    fn write(&self, dst: &mut Write) -> io::Result<()> {
-        let len = <Var<i32> as Protocol>::proto_len(&self.id()) + self.len();
-        try!(<Var<i32> as Protocol>::proto_encode(&(len as i32), dst));
-        try!(<Var<i32> as Protocol>::proto_encode(&self.id(), dst));
-        try!(self.encode(dst));
-        Ok(())
+    /// `compression` is the threshold set by the last `SetCompression` packet of the connection,
+    /// or `None` if compression has not been enabled. With a threshold, packets of at least that
+    /// many bytes (ID included) are zlib-compressed, and smaller ones are sent with a data length of 0.
+    fn write(&self, dst: &mut Write, compression: Option<usize>) -> Result<()> {
+        let mut body = Vec::new();
+        try!(self.encode(&mut body).map_err(|err| err.in_packet(self.name())));
+        write_frame(self.id(), &body, dst, compression)
+    }
+}
+
+/// Writes a packet ID and an encoded packet body to a writer as a single frame.
+///
+/// `compression` has the same meaning as for `Packet::write`.
+pub fn write_frame(id: i32, body: &[u8], dst: &mut Write, compression: Option<usize>) -> Result<()> {
+    let len = <Var<i32> as Protocol>::proto_len(&id) + body.len();
+    match compression {
+        None => {
+            try!(<Var<i32> as Protocol>::proto_encode(&(len as i32), dst));
+            try!(<Var<i32> as Protocol>::proto_encode(&id, dst));
+            try!(dst.write_all(body));
+        }
+        Some(threshold) if len < threshold => {
+            // The data length is always 0 here, which takes up a single byte.
+            try!(<Var<i32> as Protocol>::proto_encode(&(len as i32 + 1), dst));
+            try!(<Var<i32> as Protocol>::proto_encode(&0, dst));
+            try!(<Var<i32> as Protocol>::proto_encode(&id, dst));
+            try!(dst.write_all(body));
+        }
+        Some(_) => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::Default);
+            try!(<Var<i32> as Protocol>::proto_encode(&id, &mut encoder));
+            try!(encoder.write_all(body));
+            let data = try!(encoder.finish());
+            let frame_len = <Var<i32> as Protocol>::proto_len(&(len as i32)) + data.len();
+            try!(<Var<i32> as Protocol>::proto_encode(&(frame_len as i32), dst));
+            try!(<Var<i32> as Protocol>::proto_encode(&(len as i32), dst));
+            try!(dst.write_all(&data));
+        }
+    }
+    Ok(())
+}
+
+/// Reads a single length-prefixed frame from a reader, returning the packet ID and body.
+///
+/// `compression` has the same meaning as for `Packet::write`.
+pub fn read_frame(src: &mut Read, compression: Option<usize>) -> Result<Vec<u8>> {
+    let len = try!(<Var<i32> as Protocol>::proto_decode(src));
+    if len < 0 {
+        return Err(Error::invalid("invalid frame length", Some(format!("frame length must not be negative, found {}", len))));
+    }
+    try!(limits::check("frame size", len as usize, limits::current().max_frame_size));
+    let frame = try!(src.read_exact(len as usize));
+    inflate_frame(frame, compression)
+}
+
+/// A reader over a packet body which remembers attempts to read past its end.
+struct BodyReader<'a> {
+    body: &'a [u8],
+    overrun: bool
+}
+
+impl<'a> Read for BodyReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.body.is_empty() && !buf.is_empty() {
+            self.overrun = true;
+        }
+        self.body.read(buf)
+    }
+}
+
+/// Decodes a packet body which must be exactly as long as `body`.
+fn decode_body<P: Packet>(name: &'static str, body: &[u8]) -> Result<P> {
+    decode_body_with(name, body, <P as Packet>::decode)
+}
+
+/// Decodes a packet body with a custom decoding function, which is given a reader over the
+/// body and its length, and must read exactly the whole body.
+pub fn decode_body_with<P, F>(name: &'static str, body: &[u8], decode: F) -> Result<P>
+    where F: FnOnce(&mut Read, usize) -> Result<P>
+{
+    let _limits = limits::packet_scope(name);
+    let mut src = BodyReader { body: body, overrun: false };
+    match decode(&mut src, body.len()) {
+        Ok(_) if !src.body.is_empty() => {
+            Err(Error::invalid("packet shorter than frame", Some(format!("{} of {} bytes left unread", src.body.len(), body.len()))).in_packet(name))
+        }
+        Err(err) if src.overrun => {
+            // Keep the path of the field which ran out of data.
+            let mut overrun = Error::invalid("packet longer than frame", Some(format!("tried to read past the end of its {} bytes", body.len()))).in_packet(name);
+            overrun.path = err.path;
+            Err(overrun)
+        }
+        result => result.map_err(|err| err.in_packet(name))
     }
 }
 
+/// Decodes a field of a packet with the limits configured for it in `limits`.
+///
+/// This is used by the packets generated by `packet!` as well as the hand-written ones, so that
+/// field limits apply to every packet.
+pub fn decode_field<T: Protocol>(packet: &'static str, field: &'static str, src: &mut Read) -> Result<T::Clean> {
+    let _limits = limits::field_scope(packet, field);
+    <T as Protocol>::proto_decode(src).map_err(|err| err.in_field(field))
+}
+
+/// Strips the data length from a frame in the compressed format and decompresses it if needed.
+///
+/// Frames are returned unchanged if compression is disabled. Like vanilla, this rejects
+/// compressed packets smaller than the threshold, and compressed data which doesn't end after
+/// the given data length.
+pub fn inflate_frame(frame: Vec<u8>, compression: Option<usize>) -> Result<Vec<u8>> {
+    let threshold = match compression {
+        Some(threshold) => threshold,
+        None => return Ok(frame)
+    };
+    let mut src = io::Cursor::new(frame);
+    let data_len = try!(<Var<i32> as Protocol>::proto_decode(&mut src));
+    let start = src.position() as usize;
+    let frame = src.into_inner();
+    if data_len == 0 {
+        Ok(frame[start..].to_vec())
+    } else if data_len < 0 {
+        Err(Error::invalid("invalid data length", Some(format!("data length must not be negative, found {}", data_len))))
+    } else if (data_len as usize) < threshold {
+        Err(Error::invalid("badly compressed packet", Some(format!("data length {} is below the compression threshold of {}", data_len, threshold))))
+    } else {
+        try!(limits::check("packet size", data_len as usize, limits::current().max_packet_size));
+        let mut decoder = ZlibDecoder::new(&frame[start..]);
+        let data = try!(decoder.read_exact(data_len as usize));
+        if try!(decoder.read(&mut [0])) != 0 {
+            return Err(Error::invalid("badly compressed packet", Some(format!("data continues past its length of {}", data_len))));
+        }
+        Ok(data)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Direction {
     Clientbound,
     Serverbound
 }
 
+/// Describes a packet declared with the `packets!` macro.
+#[derive(Debug)]
+pub struct PacketInfo {
+    pub state: State,
+    pub direction: Direction,
+    pub id: i32,
+    /// The name of the packet struct.
+    pub name: &'static str,
+    /// The names of the packet fields along with their types as written in the declaration.
+    pub fields: &'static [(&'static str, &'static str)]
+}
+
+/// Returns the description of the packet with the given ID, if there is one.
+pub fn packet_info(state: State, direction: Direction, id: i32) -> Option<&'static PacketInfo> {
+    PACKETS.iter().find(|info| info.state == state && info.direction == direction && info.id == id)
+}
+
+macro_rules! packet_fields {
+    () => { &[] };
+    ($($fname:ident: $fty:ty),+) => {
+        &[$((stringify!($fname), stringify!($fty))),+]
+    };
+    ($($fname:ident: $fty:ty),+; $impl_packet:item) => {
+        &[$((stringify!($fname), stringify!($fty))),+]
+    }
+}
+
 macro_rules! packet {
     // Regular packets
-    ($name:ident ($id:expr) { $($fname:ident: $fty:ty),+ }) => {
+    ($name:ident ($id:expr, $state:ident, $direction:ident) { $($fname:ident: $fty:ty),+ }) => {
         #[derive(Debug)]
         pub struct $name {
             $(pub $fname: <$fty as Protocol>::Clean),*
@@ -67,6 +236,12 @@ macro_rules! packet {
         impl PacketBase for $name {
             #[allow(unused_variables)]
             fn id(&self) -> i32 { $id }
+            #[allow(unused_variables)]
+            fn name(&self) -> &'static str { stringify!($name) }
+            #[allow(unused_variables)]
+            fn state(&self) -> State { State::$state }
+            #[allow(unused_variables)]
+            fn direction(&self) -> Direction { Direction::$direction }
         }
 
         impl Packet for $name {
@@ -74,27 +249,33 @@ macro_rules! packet {
                 0 $(+ <$fty as Protocol>::proto_len(&self.$fname) as usize)*
             }
 
-            fn encode(&self, mut dst: &mut Write) -> io::Result<()> {
-                $(try!(<$fty as Protocol>::proto_encode(&self.$fname, dst));)*
+            fn encode(&self, mut dst: &mut Write) -> Result<()> {
+                $(try!(<$fty as Protocol>::proto_encode(&self.$fname, dst).map_err(|err| err.in_field(stringify!($fname))));)*
                 Ok(())
             }
 
             #[allow(unused_variables)]
-            fn decode(mut src: &mut Read, len: usize) -> io::Result<$name> {
+            fn decode(mut src: &mut Read, len: usize) -> Result<$name> {
                 Ok($name {
-                    $($fname: try!(<$fty as Protocol>::proto_decode(src))),*
+                    $($fname: try!(decode_field::<$fty>(stringify!($name), stringify!($fname), src))),*
                 })
             }
         }
     };
     // No field packets
-    ($name:ident ($id:expr) {}) => {
+    ($name:ident ($id:expr, $state:ident, $direction:ident) {}) => {
         #[derive(Debug)]
         pub struct $name;
 
         impl PacketBase for $name {
             #[allow(unused_variables)]
             fn id(&self) -> i32 { $id }
+            #[allow(unused_variables)]
+            fn name(&self) -> &'static str { stringify!($name) }
+            #[allow(unused_variables)]
+            fn state(&self) -> State { State::$state }
+            #[allow(unused_variables)]
+            fn direction(&self) -> Direction { Direction::$direction }
         }
 
         impl Packet for $name {
@@ -102,18 +283,18 @@ macro_rules! packet {
             fn len(&self) -> usize { 0 }
 
             #[allow(unused_variables)]
-            fn encode(&self, dst: &mut Write) -> io::Result<()> {
+            fn encode(&self, dst: &mut Write) -> Result<()> {
                 Ok(())
             }
 
             #[allow(unused_variables)]
-            fn decode(src: &mut Read, len: usize) -> io::Result<$name> {
+            fn decode(src: &mut Read, len: usize) -> Result<$name> {
                 Ok($name)
             }
         }
     };
     // Custom encode/decode packets
-    ($name:ident ($id:expr) { $($fname:ident: $fty:ty),+; $impl_packet:item }) => {
+    ($name:ident ($id:expr, $state:ident, $direction:ident) { $($fname:ident: $fty:ty),+; $impl_packet:item }) => {
         pub struct $name {
             $(pub $fname: $fty),*
         }
@@ -121,58 +302,112 @@ macro_rules! packet {
         impl PacketBase for $name {
             #[allow(unused_variables)]
             fn id(&self) -> i32 { $id }
+            #[allow(unused_variables)]
+            fn name(&self) -> &'static str { stringify!($name) }
+            #[allow(unused_variables)]
+            fn state(&self) -> State { State::$state }
+            #[allow(unused_variables)]
+            fn direction(&self) -> Direction { Direction::$direction }
         }
 
         $impl_packet
     }
 }
 
+/// Implements `Packet`-like methods on a `PacketEnum` by delegating to the wrapped value.
+macro_rules! packet_enum_impl {
+    ($($variant:ident),*) => {
+        impl PacketEnum {
+            /// The ID of the wrapped packet.
+            pub fn id(&self) -> i32 {
+                match *self {
+                    $(PacketEnum::$variant(ref packet) => packet.id()),*
+                }
+            }
+
+            /// The length of the wrapped packet's fields, in bytes.
+            pub fn len(&self) -> usize {
+                match *self {
+                    $(PacketEnum::$variant(ref packet) => packet.len()),*
+                }
+            }
+
+            /// Encodes the wrapped packet's body and writes it to a writer.
+            pub fn encode(&self, dst: &mut Write) -> Result<()> {
+                match *self {
+                    $(PacketEnum::$variant(ref packet) => packet.encode(dst)),*
+                }
+            }
+
+            /// Writes the wrapped packet to a writer, like `Packet::write`.
+            pub fn write(&self, dst: &mut Write, compression: Option<usize>) -> Result<()> {
+                match *self {
+                    $(PacketEnum::$variant(ref packet) => packet.write(dst, compression)),*
+                }
+            }
+        }
+    }
+}
+
 macro_rules! packets {
     ($($state:ident => $state_mod:ident { clientbound { $($c_id:expr => $c_name:ident { $($c_packet:tt)* })* } serverbound { $($s_id:expr => $s_name:ident { $($s_packet:tt)* })* } })*) => {
         $(
             pub mod $state_mod {
+                use error::Result;
+                use std::io::prelude::*;
+
                 pub mod clientbound {
                     #![allow(unused_imports)]
-                    use packet::{BlockChangeRecord, Packet, PacketBase, Protocol, Stat, State};
+                    use packet::{BlockChangeRecord, ChunkMeta, CombatEvent, Direction, EntityUseAction, ObjectData, ObjectiveAction, Packet, PacketBase, PlayerDisplayName, PlayerGamemode, PlayerInfo, PlayerLatency, PlayerListAction, Property, Protocol, ScoreAction, Stat, State, TeamAction, TitleAction, WorldBorderAction, decode_field};
                     use types::consts::*;
-                    use types::{Arr, BlockPos, NbtBlob, Slot, Var};
+                    use types::{Angle, Arr, BlockPos, Chat, ChunkColumn, Fixed, MapData, MapIcon, Metadata, NbtBlob, OptionalMapData, OptionalNbt, ParticleType, Slot, UuidString, Var, WindowType};
 
+                    use error::{Error, ErrorKind, Result};
+                    use std::error::FromError;
                     use std::io;
                     use std::io::prelude::*;
 
                     use util::ReadExactExt;
                     use uuid::Uuid;
 
-                    $(packet!{ $c_name ($c_id) { $($c_packet)* } })*
+                    $(packet!{ $c_name ($c_id, $state, Clientbound) { $($c_packet)* } })*
 
                     pub enum PacketEnum {
                         $($c_name($c_name)),*
                     }
+
+                    packet_enum_impl!($($c_name),*);
                 }
 
                 pub mod serverbound {
                     #![allow(unused_imports)]
-                    use packet::{BlockChangeRecord, Packet, PacketBase, Protocol, Stat, State};
+                    use packet::{BlockChangeRecord, ChunkMeta, CombatEvent, Direction, EntityUseAction, ObjectData, ObjectiveAction, Packet, PacketBase, PlayerDisplayName, PlayerGamemode, PlayerInfo, PlayerLatency, PlayerListAction, Property, Protocol, ScoreAction, Stat, State, TeamAction, TitleAction, WorldBorderAction, decode_field};
                     use types::consts::*;
-                    use types::{Arr, BlockPos, NbtBlob, Slot, Var};
+                    use types::{Angle, Arr, BlockPos, Chat, ChunkColumn, Fixed, MapData, MapIcon, Metadata, NbtBlob, OptionalMapData, OptionalNbt, ParticleType, Slot, UuidString, Var, WindowType};
 
+                    use error::{Error, ErrorKind, Result};
+                    use std::error::FromError;
                     use std::io;
                     use std::io::prelude::*;
 
                     use util::ReadExactExt;
                     use uuid::Uuid;
 
-                    $(packet!{ $s_name ($s_id) { $($s_packet)* } })*
+                    $(packet!{ $s_name ($s_id, $state, Serverbound) { $($s_packet)* } })*
 
                     pub enum PacketEnum {
                         $($s_name($s_name)),*
                     }
+
+                    packet_enum_impl!($($s_name),*);
                 }
 
                 pub enum PacketEnum {
                     Clientbound(clientbound::PacketEnum),
                     Serverbound(serverbound::PacketEnum)
                 }
+
+                packet_enum_impl!(Clientbound, Serverbound);
             }
         )*
 
@@ -180,27 +415,57 @@ macro_rules! packets {
             $($state($state_mod::PacketEnum)),*
         }
 
-        #[derive(Debug)]
+        packet_enum_impl!($($state),*);
+
+        #[derive(Clone, Copy, Debug, PartialEq)]
         pub enum State {
             $($state),*
         }
 
+        /// Every packet declared in this module, in declaration order.
+        pub static PACKETS: &'static [PacketInfo] = &[
+            $(
+                $(PacketInfo {
+                    state: State::$state,
+                    direction: Direction::Clientbound,
+                    id: $c_id,
+                    name: stringify!($c_name),
+                    fields: packet_fields!($($c_packet)*)
+                },)*
+                $(PacketInfo {
+                    state: State::$state,
+                    direction: Direction::Serverbound,
+                    id: $s_id,
+                    name: stringify!($s_name),
+                    fields: packet_fields!($($s_packet)*)
+                },)*
+            )*
+        ];
+
         /// Reads a new packet from a reader, wrapping in an enum for exhaustive matching.
         ///
-        /// **TODO:** add support for compression.
-        fn read_packet(direction: Direction, state: State, mut src: &mut Read) -> io::Result<PacketEnum> {
-            let proto_len = try!(<Var<i32> as Protocol>::proto_decode(src));
-            let id = try!(<Var<i32> as Protocol>::proto_decode(src));
-            let len = proto_len as usize - <Var<i32> as Protocol>::proto_len(&id);
+        /// `compression` has the same meaning as for `Packet::write`.
+        pub fn read_packet(direction: Direction, state: State, compression: Option<usize>, src: &mut Read) -> Result<PacketEnum> {
+            let frame = try!(read_frame(src, compression));
+            decode_packet(direction, state, &frame)
+        }
+
+        /// Decodes a packet from the uncompressed contents of a frame (the packet ID and body).
+        ///
+        /// The packet must use up the whole frame: both leftover bytes and attempts to read past
+        /// the end of the frame are reported as errors.
+        pub fn decode_packet(direction: Direction, state: State, frame: &[u8]) -> Result<PacketEnum> {
+            let mut src = frame;
+            let id = try!(<Var<i32> as Protocol>::proto_decode(&mut src));
             match state {
                 $(State::$state => match direction {
                     Direction::Clientbound => match id {
-                        $($c_id => Ok(PacketEnum::$state($state_mod::PacketEnum::Clientbound($state_mod::clientbound::PacketEnum::$c_name(try!(<$state_mod::clientbound::$c_name as Packet>::decode(src, len)))))),)*
-                        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "unknown packet id for clientbound packet", None))
+                        $($c_id => decode_body::<$state_mod::clientbound::$c_name>(stringify!($c_name), src).map(|packet| PacketEnum::$state($state_mod::PacketEnum::Clientbound($state_mod::clientbound::PacketEnum::$c_name(packet)))),)*
+                        _ => Err(Error::new(ErrorKind::UnknownPacketId { state: state, direction: direction, id: id }))
                     },
                     Direction::Serverbound => match id {
-                        $($s_id => Ok(PacketEnum::$state($state_mod::PacketEnum::Serverbound($state_mod::serverbound::PacketEnum::$s_name(try!(<$state_mod::serverbound::$s_name as Packet>::decode(src, len)))))),)*
-                        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "unknown packet id for serverbound packet", None))
+                        $($s_id => decode_body::<$state_mod::serverbound::$s_name>(stringify!($s_name), src).map(|packet| PacketEnum::$state($state_mod::PacketEnum::Serverbound($state_mod::serverbound::PacketEnum::$s_name(packet)))),)*
+                        _ => Err(Error::new(ErrorKind::UnknownPacketId { state: state, direction: direction, id: id }))
                     }
                 }),*
             }
@@ -216,12 +481,12 @@ macro_rules! impl_protocol {
             #[allow(unused_variables)]
             fn proto_len(value: &$name) -> usize { 1 }
 
-            fn proto_encode(value: &$name, mut dst: &mut Write) -> io::Result<()> {
+            fn proto_encode(value: &$name, mut dst: &mut Write) -> Result<()> {
                 try!(dst.$enc_name(*value));
                 Ok(())
             }
 
-            fn proto_decode(mut src: &mut Read) -> io::Result<$name> {
+            fn proto_decode(mut src: &mut Read) -> Result<$name> {
                 src.$dec_name().map_err(|err| FromError::from_error(err))
             }
         }
@@ -233,12 +498,12 @@ macro_rules! impl_protocol {
             #[allow(unused_variables)]
             fn proto_len(value: &$name) -> usize { $len }
 
-            fn proto_encode(value: &$name, mut dst: &mut Write) -> io::Result<()> {
+            fn proto_encode(value: &$name, mut dst: &mut Write) -> Result<()> {
                 try!(dst.$enc_name::<BigEndian>(*value));
                 Ok(())
             }
 
-            fn proto_decode(mut src: &mut Read) -> io::Result<$name> {
+            fn proto_decode(mut src: &mut Read) -> Result<$name> {
                 src.$dec_name::<BigEndian>().map_err(|err| FromError::from_error(err))
             }
         }
@@ -259,14 +524,14 @@ macro_rules! proto_struct {
                 0 $(+ <$fty as Protocol>::proto_len(&value.$fname) as usize)*
             }
 
-            fn proto_encode(value: &$name, dst: &mut Write) -> io::Result<()> {
-                $(try!(<$fty as Protocol>::proto_encode(&value.$fname, dst));)*
+            fn proto_encode(value: &$name, dst: &mut Write) -> Result<()> {
+                $(try!(<$fty as Protocol>::proto_encode(&value.$fname, dst).map_err(|err| err.in_field(stringify!($fname))));)*
                 Ok(())
             }
 
-            fn proto_decode(mut src: &mut Read) -> io::Result<$name> {
+            fn proto_decode(mut src: &mut Read) -> Result<$name> {
                 Ok($name {
-                    $($fname: try!(<$fty as Protocol>::proto_decode(src))),*
+                    $($fname: try!(<$fty as Protocol>::proto_decode(src).map_err(|err| err.in_field(stringify!($fname))))),*
                 })
             }
         }
@@ -296,15 +561,15 @@ impl Protocol for bool {
     #[allow(unused_variables)]
     fn proto_len(value: &bool) -> usize { 1 }
 
-    fn proto_encode(value: &bool, mut dst: &mut Write) -> io::Result<()> {
+    fn proto_encode(value: &bool, mut dst: &mut Write) -> Result<()> {
         try!(dst.write_u8(if *value { 1 } else { 0 }));
         Ok(())
     }
 
-    fn proto_decode(mut src: &mut Read) -> io::Result<bool> {
+    fn proto_decode(mut src: &mut Read) -> Result<bool> {
         let value = try!(src.read_u8());
         if value > 1 {
-            Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid bool value", Some(format!("Invalid bool value, expecting 0 or 1, got {}", value))))
+            Err(Error::invalid("invalid bool value", Some(format!("Invalid bool value, expecting 0 or 1, got {}", value))))
         } else {
             Ok(value == 1)
         }
@@ -322,7 +587,7 @@ impl<T: Protocol> Protocol for Option<T> {
         }
     }
 
-    fn proto_encode(value: &Option<T::Clean>, dst: &mut Write) -> io::Result<()> {
+    fn proto_encode(value: &Option<T::Clean>, dst: &mut Write) -> Result<()> {
         match *value {
             Some(ref inner) => {
                 try!(<bool as Protocol>::proto_encode(&true, dst));
@@ -335,7 +600,7 @@ impl<T: Protocol> Protocol for Option<T> {
         Ok(())
     }
 
-    fn proto_decode(src: &mut Read) -> io::Result<Option<T::Clean>> {
+    fn proto_decode(src: &mut Read) -> Result<Option<T::Clean>> {
         if try!(<bool as Protocol>::proto_decode(src)) {
             Ok(Some(try!(<T as Protocol>::proto_decode(src))))
         } else {
@@ -353,25 +618,64 @@ impl Protocol for State {
     #[allow(unused_variables)]
     fn proto_len(value: &State) -> usize { 1 }
 
-    fn proto_encode(value: &State, dst: &mut Write) -> io::Result<()> {
+    fn proto_encode(value: &State, dst: &mut Write) -> Result<()> {
         let i = match *value {
             State::Status => 1,
             State::Login => 2,
-            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid state", None))
+            _ => return Err(Error::invalid("invalid state", None))
         };
         try!(<Var<i32> as Protocol>::proto_encode(&i, dst));
         Ok(())
     }
 
-    fn proto_decode(src: &mut Read) -> io::Result<State> {
+    fn proto_decode(src: &mut Read) -> Result<State> {
         match try!(<Var<i32> as Protocol>::proto_decode(src)) {
             1 => Ok(State::Status),
             2 => Ok(State::Login),
-            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid state", None))
+            value => Err(Error::new(ErrorKind::InvalidEnumValue { name: "State", value: value as i64 }))
         }
     }
 }
 
+/// The data field of the `SpawnObject` packet, whose meaning depends on the object type.
+///
+/// The velocity is sent if and only if `data` is not 0, so it must be `Some` exactly then.
+#[derive(Debug)]
+pub struct ObjectData {
+    pub data: i32,
+    pub velocity: Option<[i16; 3]>
+}
+
+impl Protocol for ObjectData {
+    type Clean = ObjectData;
+
+    fn proto_len(value: &ObjectData) -> usize {
+        if value.data == 0 { 4 } else { 4 + 6 }
+    }
+
+    fn proto_encode(value: &ObjectData, dst: &mut Write) -> Result<()> {
+        match (value.data, value.velocity) {
+            (0, Some(_)) => Err(Error::invalid("invalid object data", Some("velocity can't be sent when data is 0".to_string()))),
+            (0, None) => <i32 as Protocol>::proto_encode(&value.data, dst),
+            (_, None) => Err(Error::invalid("invalid object data", Some("velocity must be sent when data is not 0".to_string()))),
+            (_, Some(ref velocity)) => {
+                try!(<i32 as Protocol>::proto_encode(&value.data, dst));
+                <[i16; 3] as Protocol>::proto_encode(velocity, dst)
+            }
+        }
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<ObjectData> {
+        let data = try!(<i32 as Protocol>::proto_decode(src));
+        let velocity = if data != 0 {
+            Some(try!(<[i16; 3] as Protocol>::proto_decode(src)))
+        } else {
+            None
+        };
+        Ok(ObjectData { data: data, velocity: velocity })
+    }
+}
+
 proto_structs! {
     BlockChangeRecord {
         xz: u8,
@@ -383,20 +687,573 @@ proto_structs! {
         name: String,
         value: Var<i32>
     }
+
+    ChunkMeta {
+        x: i32,
+        z: i32,
+        mask: u16
+    }
+
+    PlayerProperty {
+        name: String,
+        value: String,
+        signature: Option<String>
+    }
+
+    PlayerInfo {
+        uuid: Uuid,
+        name: String,
+        properties: Arr<Var<i32>, PlayerProperty>,
+        gamemode: Var<i32>,
+        ping: Var<i32>,
+        display_name: Option<Chat>
+    }
+
+    PlayerGamemode {
+        uuid: Uuid,
+        gamemode: Var<i32>
+    }
+
+    PlayerLatency {
+        uuid: Uuid,
+        ping: Var<i32>
+    }
+
+    PlayerDisplayName {
+        uuid: Uuid,
+        display_name: Option<Chat>
+    }
+
+    TeamInfo {
+        display_name: String,
+        prefix: String,
+        suffix: String,
+        friendly_fire: i8,
+        name_tag_visibility: String,
+        color: i8
+    }
+
+    Modifier {
+        uuid: Uuid,
+        amount: f64,
+        operation: ModifierOperation
+    }
+
+    Property {
+        key: String,
+        value: f64,
+        modifiers: Arr<Var<i32>, Modifier>
+    }
+}
+
+/// The players listed in an `UpdatePlayerList` packet, with the information updated for each.
+#[derive(Debug)]
+pub enum PlayerListAction {
+    AddPlayer(Vec<PlayerInfo>),
+    UpdateGamemode(Vec<PlayerGamemode>),
+    UpdateLatency(Vec<PlayerLatency>),
+    UpdateDisplayName(Vec<PlayerDisplayName>),
+    RemovePlayer(Vec<Uuid>)
+}
+
+/// How the scores of an objective are displayed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ObjectiveType {
+    Integer,
+    Hearts
+}
+
+impl Protocol for ObjectiveType {
+    type Clean = ObjectiveType;
+
+    fn proto_len(value: &ObjectiveType) -> usize {
+        match *value {
+            ObjectiveType::Integer => 1 + 7,
+            ObjectiveType::Hearts => 1 + 6
+        }
+    }
+
+    fn proto_encode(value: &ObjectiveType, dst: &mut Write) -> Result<()> {
+        let name = match *value {
+            ObjectiveType::Integer => "integer",
+            ObjectiveType::Hearts => "hearts"
+        };
+        <String as Protocol>::proto_encode(&name.to_string(), dst)
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<ObjectiveType> {
+        let name = try!(<String as Protocol>::proto_decode(src));
+        match &name[..] {
+            "integer" => Ok(ObjectiveType::Integer),
+            "hearts" => Ok(ObjectiveType::Hearts),
+            _ => Err(Error::invalid("invalid objective type", Some(format!("unknown objective type {}", name))))
+        }
+    }
+}
+
+/// The change made by a `ScoreboardObjective` packet, encoded as a mode byte and its fields.
+#[derive(Debug)]
+pub enum ObjectiveAction {
+    Create { display_name: String, kind: ObjectiveType },
+    Remove,
+    Update { display_name: String, kind: ObjectiveType }
+}
+
+impl Protocol for ObjectiveAction {
+    type Clean = ObjectiveAction;
+
+    fn proto_len(value: &ObjectiveAction) -> usize {
+        match *value {
+            ObjectiveAction::Create { ref display_name, ref kind } |
+            ObjectiveAction::Update { ref display_name, ref kind } => {
+                1 + <String as Protocol>::proto_len(display_name) + <ObjectiveType as Protocol>::proto_len(kind)
+            }
+            ObjectiveAction::Remove => 1
+        }
+    }
+
+    fn proto_encode(value: &ObjectiveAction, dst: &mut Write) -> Result<()> {
+        match *value {
+            ObjectiveAction::Create { ref display_name, ref kind } => {
+                try!(<i8 as Protocol>::proto_encode(&0, dst));
+                try!(<String as Protocol>::proto_encode(display_name, dst));
+                try!(<ObjectiveType as Protocol>::proto_encode(kind, dst));
+            }
+            ObjectiveAction::Remove => {
+                try!(<i8 as Protocol>::proto_encode(&1, dst));
+            }
+            ObjectiveAction::Update { ref display_name, ref kind } => {
+                try!(<i8 as Protocol>::proto_encode(&2, dst));
+                try!(<String as Protocol>::proto_encode(display_name, dst));
+                try!(<ObjectiveType as Protocol>::proto_encode(kind, dst));
+            }
+        }
+        Ok(())
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<ObjectiveAction> {
+        match try!(<i8 as Protocol>::proto_decode(src)) {
+            0 => Ok(ObjectiveAction::Create {
+                display_name: try!(<String as Protocol>::proto_decode(src)),
+                kind: try!(<ObjectiveType as Protocol>::proto_decode(src))
+            }),
+            1 => Ok(ObjectiveAction::Remove),
+            2 => Ok(ObjectiveAction::Update {
+                display_name: try!(<String as Protocol>::proto_decode(src)),
+                kind: try!(<ObjectiveType as Protocol>::proto_decode(src))
+            }),
+            mode => Err(Error::new(ErrorKind::InvalidEnumValue { name: "ObjectiveAction", value: mode as i64 }))
+        }
+    }
+}
+
+/// The change made by an `UpdateScore` packet, encoded as an action byte and its fields.
+#[derive(Debug)]
+pub enum ScoreAction {
+    Change { objective: String, value: i32 },
+    Remove { objective: String }
+}
+
+impl Protocol for ScoreAction {
+    type Clean = ScoreAction;
+
+    fn proto_len(value: &ScoreAction) -> usize {
+        match *value {
+            ScoreAction::Change { ref objective, ref value } => 1 + <String as Protocol>::proto_len(objective) + <Var<i32> as Protocol>::proto_len(value),
+            ScoreAction::Remove { ref objective } => 1 + <String as Protocol>::proto_len(objective)
+        }
+    }
+
+    fn proto_encode(value: &ScoreAction, dst: &mut Write) -> Result<()> {
+        match *value {
+            ScoreAction::Change { ref objective, ref value } => {
+                try!(<i8 as Protocol>::proto_encode(&0, dst));
+                try!(<String as Protocol>::proto_encode(objective, dst));
+                try!(<Var<i32> as Protocol>::proto_encode(value, dst));
+            }
+            ScoreAction::Remove { ref objective } => {
+                try!(<i8 as Protocol>::proto_encode(&1, dst));
+                try!(<String as Protocol>::proto_encode(objective, dst));
+            }
+        }
+        Ok(())
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<ScoreAction> {
+        match try!(<i8 as Protocol>::proto_decode(src)) {
+            0 => Ok(ScoreAction::Change {
+                objective: try!(<String as Protocol>::proto_decode(src)),
+                value: try!(<Var<i32> as Protocol>::proto_decode(src))
+            }),
+            1 => Ok(ScoreAction::Remove {
+                objective: try!(<String as Protocol>::proto_decode(src))
+            }),
+            action => Err(Error::new(ErrorKind::InvalidEnumValue { name: "ScoreAction", value: action as i64 }))
+        }
+    }
+}
+
+/// The change made by an `UpdateTeam` packet, encoded as a mode byte and its fields.
+#[derive(Debug)]
+pub enum TeamAction {
+    Create { info: TeamInfo, players: Vec<String> },
+    Remove,
+    UpdateInfo(TeamInfo),
+    AddPlayers(Vec<String>),
+    RemovePlayers(Vec<String>)
+}
+
+impl Protocol for TeamAction {
+    type Clean = TeamAction;
+
+    fn proto_len(value: &TeamAction) -> usize {
+        1 + match *value {
+            TeamAction::Create { ref info, ref players } => <TeamInfo as Protocol>::proto_len(info) + <Arr<Var<i32>, String> as Protocol>::proto_len(players),
+            TeamAction::Remove => 0,
+            TeamAction::UpdateInfo(ref info) => <TeamInfo as Protocol>::proto_len(info),
+            TeamAction::AddPlayers(ref players) |
+            TeamAction::RemovePlayers(ref players) => <Arr<Var<i32>, String> as Protocol>::proto_len(players)
+        }
+    }
+
+    fn proto_encode(value: &TeamAction, dst: &mut Write) -> Result<()> {
+        match *value {
+            TeamAction::Create { ref info, ref players } => {
+                try!(<i8 as Protocol>::proto_encode(&0, dst));
+                try!(<TeamInfo as Protocol>::proto_encode(info, dst));
+                try!(<Arr<Var<i32>, String> as Protocol>::proto_encode(players, dst));
+            }
+            TeamAction::Remove => {
+                try!(<i8 as Protocol>::proto_encode(&1, dst));
+            }
+            TeamAction::UpdateInfo(ref info) => {
+                try!(<i8 as Protocol>::proto_encode(&2, dst));
+                try!(<TeamInfo as Protocol>::proto_encode(info, dst));
+            }
+            TeamAction::AddPlayers(ref players) => {
+                try!(<i8 as Protocol>::proto_encode(&3, dst));
+                try!(<Arr<Var<i32>, String> as Protocol>::proto_encode(players, dst));
+            }
+            TeamAction::RemovePlayers(ref players) => {
+                try!(<i8 as Protocol>::proto_encode(&4, dst));
+                try!(<Arr<Var<i32>, String> as Protocol>::proto_encode(players, dst));
+            }
+        }
+        Ok(())
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<TeamAction> {
+        match try!(<i8 as Protocol>::proto_decode(src)) {
+            0 => Ok(TeamAction::Create {
+                info: try!(<TeamInfo as Protocol>::proto_decode(src)),
+                players: try!(<Arr<Var<i32>, String> as Protocol>::proto_decode(src))
+            }),
+            1 => Ok(TeamAction::Remove),
+            2 => Ok(TeamAction::UpdateInfo(try!(<TeamInfo as Protocol>::proto_decode(src)))),
+            3 => Ok(TeamAction::AddPlayers(try!(<Arr<Var<i32>, String> as Protocol>::proto_decode(src)))),
+            4 => Ok(TeamAction::RemovePlayers(try!(<Arr<Var<i32>, String> as Protocol>::proto_decode(src)))),
+            mode => Err(Error::new(ErrorKind::InvalidEnumValue { name: "TeamAction", value: mode as i64 }))
+        }
+    }
+}
+
+/// The event reported by a `PlayCombatEvent` packet, encoded as a VarInt event id and its fields.
+#[derive(Debug)]
+pub enum CombatEvent {
+    EnterCombat,
+    EndCombat { duration: i32, entity_id: i32 },
+    EntityDead { player_id: i32, entity_id: i32, message: String }
+}
+
+impl Protocol for CombatEvent {
+    type Clean = CombatEvent;
+
+    fn proto_len(value: &CombatEvent) -> usize {
+        match *value {
+            CombatEvent::EnterCombat => 1,
+            CombatEvent::EndCombat { ref duration, .. } => 1 + <Var<i32> as Protocol>::proto_len(duration) + 4,
+            CombatEvent::EntityDead { ref player_id, ref message, .. } => {
+                1 + <Var<i32> as Protocol>::proto_len(player_id) + 4 + <String as Protocol>::proto_len(message)
+            }
+        }
+    }
+
+    fn proto_encode(value: &CombatEvent, dst: &mut Write) -> Result<()> {
+        match *value {
+            CombatEvent::EnterCombat => {
+                try!(<Var<i32> as Protocol>::proto_encode(&0, dst));
+            }
+            CombatEvent::EndCombat { ref duration, ref entity_id } => {
+                try!(<Var<i32> as Protocol>::proto_encode(&1, dst));
+                try!(<Var<i32> as Protocol>::proto_encode(duration, dst));
+                try!(<i32 as Protocol>::proto_encode(entity_id, dst));
+            }
+            CombatEvent::EntityDead { ref player_id, ref entity_id, ref message } => {
+                try!(<Var<i32> as Protocol>::proto_encode(&2, dst));
+                try!(<Var<i32> as Protocol>::proto_encode(player_id, dst));
+                try!(<i32 as Protocol>::proto_encode(entity_id, dst));
+                try!(<String as Protocol>::proto_encode(message, dst));
+            }
+        }
+        Ok(())
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<CombatEvent> {
+        match try!(<Var<i32> as Protocol>::proto_decode(src)) {
+            0 => Ok(CombatEvent::EnterCombat),
+            1 => Ok(CombatEvent::EndCombat {
+                duration: try!(<Var<i32> as Protocol>::proto_decode(src)),
+                entity_id: try!(<i32 as Protocol>::proto_decode(src))
+            }),
+            2 => Ok(CombatEvent::EntityDead {
+                player_id: try!(<Var<i32> as Protocol>::proto_decode(src)),
+                entity_id: try!(<i32 as Protocol>::proto_decode(src)),
+                message: try!(<String as Protocol>::proto_decode(src))
+            }),
+            event => Err(Error::new(ErrorKind::InvalidEnumValue { name: "CombatEvent", value: event as i64 }))
+        }
+    }
+}
+
+/// The change made by a `WorldBorder` packet, encoded as a VarInt action id and its fields.
+///
+/// Lerp times (`speed`) are in real milliseconds.
+#[derive(Debug)]
+pub enum WorldBorderAction {
+    SetSize { radius: f64 },
+    LerpSize { old_radius: f64, new_radius: f64, speed: i64 },
+    SetCenter { x: f64, z: f64 },
+    Initialize {
+        x: f64,
+        z: f64,
+        old_radius: f64,
+        new_radius: f64,
+        speed: i64,
+        portal_teleport_boundary: i32,
+        warning_time: i32,
+        warning_blocks: i32
+    },
+    SetWarningTime { warning_time: i32 },
+    SetWarningBlocks { warning_blocks: i32 }
+}
+
+impl Protocol for WorldBorderAction {
+    type Clean = WorldBorderAction;
+
+    fn proto_len(value: &WorldBorderAction) -> usize {
+        1 + match *value {
+            WorldBorderAction::SetSize { .. } => 8,
+            WorldBorderAction::LerpSize { ref speed, .. } => 16 + <Var<i64> as Protocol>::proto_len(speed),
+            WorldBorderAction::SetCenter { .. } => 16,
+            WorldBorderAction::Initialize { ref speed, ref portal_teleport_boundary, ref warning_time, ref warning_blocks, .. } => {
+                32 + <Var<i64> as Protocol>::proto_len(speed)
+                   + <Var<i32> as Protocol>::proto_len(portal_teleport_boundary)
+                   + <Var<i32> as Protocol>::proto_len(warning_time)
+                   + <Var<i32> as Protocol>::proto_len(warning_blocks)
+            }
+            WorldBorderAction::SetWarningTime { ref warning_time } => <Var<i32> as Protocol>::proto_len(warning_time),
+            WorldBorderAction::SetWarningBlocks { ref warning_blocks } => <Var<i32> as Protocol>::proto_len(warning_blocks)
+        }
+    }
+
+    fn proto_encode(value: &WorldBorderAction, dst: &mut Write) -> Result<()> {
+        match *value {
+            WorldBorderAction::SetSize { ref radius } => {
+                try!(<Var<i32> as Protocol>::proto_encode(&0, dst));
+                try!(<f64 as Protocol>::proto_encode(radius, dst));
+            }
+            WorldBorderAction::LerpSize { ref old_radius, ref new_radius, ref speed } => {
+                try!(<Var<i32> as Protocol>::proto_encode(&1, dst));
+                try!(<f64 as Protocol>::proto_encode(old_radius, dst));
+                try!(<f64 as Protocol>::proto_encode(new_radius, dst));
+                try!(<Var<i64> as Protocol>::proto_encode(speed, dst));
+            }
+            WorldBorderAction::SetCenter { ref x, ref z } => {
+                try!(<Var<i32> as Protocol>::proto_encode(&2, dst));
+                try!(<f64 as Protocol>::proto_encode(x, dst));
+                try!(<f64 as Protocol>::proto_encode(z, dst));
+            }
+            WorldBorderAction::Initialize { ref x, ref z, ref old_radius, ref new_radius, ref speed, ref portal_teleport_boundary, ref warning_time, ref warning_blocks } => {
+                try!(<Var<i32> as Protocol>::proto_encode(&3, dst));
+                try!(<f64 as Protocol>::proto_encode(x, dst));
+                try!(<f64 as Protocol>::proto_encode(z, dst));
+                try!(<f64 as Protocol>::proto_encode(old_radius, dst));
+                try!(<f64 as Protocol>::proto_encode(new_radius, dst));
+                try!(<Var<i64> as Protocol>::proto_encode(speed, dst));
+                try!(<Var<i32> as Protocol>::proto_encode(portal_teleport_boundary, dst));
+                try!(<Var<i32> as Protocol>::proto_encode(warning_time, dst));
+                try!(<Var<i32> as Protocol>::proto_encode(warning_blocks, dst));
+            }
+            WorldBorderAction::SetWarningTime { ref warning_time } => {
+                try!(<Var<i32> as Protocol>::proto_encode(&4, dst));
+                try!(<Var<i32> as Protocol>::proto_encode(warning_time, dst));
+            }
+            WorldBorderAction::SetWarningBlocks { ref warning_blocks } => {
+                try!(<Var<i32> as Protocol>::proto_encode(&5, dst));
+                try!(<Var<i32> as Protocol>::proto_encode(warning_blocks, dst));
+            }
+        }
+        Ok(())
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<WorldBorderAction> {
+        match try!(<Var<i32> as Protocol>::proto_decode(src)) {
+            0 => Ok(WorldBorderAction::SetSize {
+                radius: try!(<f64 as Protocol>::proto_decode(src))
+            }),
+            1 => Ok(WorldBorderAction::LerpSize {
+                old_radius: try!(<f64 as Protocol>::proto_decode(src)),
+                new_radius: try!(<f64 as Protocol>::proto_decode(src)),
+                speed: try!(<Var<i64> as Protocol>::proto_decode(src))
+            }),
+            2 => Ok(WorldBorderAction::SetCenter {
+                x: try!(<f64 as Protocol>::proto_decode(src)),
+                z: try!(<f64 as Protocol>::proto_decode(src))
+            }),
+            3 => Ok(WorldBorderAction::Initialize {
+                x: try!(<f64 as Protocol>::proto_decode(src)),
+                z: try!(<f64 as Protocol>::proto_decode(src)),
+                old_radius: try!(<f64 as Protocol>::proto_decode(src)),
+                new_radius: try!(<f64 as Protocol>::proto_decode(src)),
+                speed: try!(<Var<i64> as Protocol>::proto_decode(src)),
+                portal_teleport_boundary: try!(<Var<i32> as Protocol>::proto_decode(src)),
+                warning_time: try!(<Var<i32> as Protocol>::proto_decode(src)),
+                warning_blocks: try!(<Var<i32> as Protocol>::proto_decode(src))
+            }),
+            4 => Ok(WorldBorderAction::SetWarningTime {
+                warning_time: try!(<Var<i32> as Protocol>::proto_decode(src))
+            }),
+            5 => Ok(WorldBorderAction::SetWarningBlocks {
+                warning_blocks: try!(<Var<i32> as Protocol>::proto_decode(src))
+            }),
+            action => Err(Error::new(ErrorKind::InvalidEnumValue { name: "WorldBorderAction", value: action as i64 }))
+        }
+    }
+}
+
+/// The change made by a `Title` packet, encoded as a VarInt action id and its fields.
+///
+/// Times are in ticks.
+#[derive(Debug)]
+pub enum TitleAction {
+    SetTitle(Chat),
+    SetSubtitle(Chat),
+    SetTimes { fade_in: i32, stay: i32, fade_out: i32 },
+    Hide,
+    Reset
+}
+
+impl Protocol for TitleAction {
+    type Clean = TitleAction;
+
+    fn proto_len(value: &TitleAction) -> usize {
+        1 + match *value {
+            TitleAction::SetTitle(ref text) |
+            TitleAction::SetSubtitle(ref text) => <Chat as Protocol>::proto_len(text),
+            TitleAction::SetTimes { .. } => 12,
+            TitleAction::Hide | TitleAction::Reset => 0
+        }
+    }
+
+    fn proto_encode(value: &TitleAction, dst: &mut Write) -> Result<()> {
+        match *value {
+            TitleAction::SetTitle(ref text) => {
+                try!(<Var<i32> as Protocol>::proto_encode(&0, dst));
+                try!(<Chat as Protocol>::proto_encode(text, dst));
+            }
+            TitleAction::SetSubtitle(ref text) => {
+                try!(<Var<i32> as Protocol>::proto_encode(&1, dst));
+                try!(<Chat as Protocol>::proto_encode(text, dst));
+            }
+            TitleAction::SetTimes { ref fade_in, ref stay, ref fade_out } => {
+                try!(<Var<i32> as Protocol>::proto_encode(&2, dst));
+                try!(<i32 as Protocol>::proto_encode(fade_in, dst));
+                try!(<i32 as Protocol>::proto_encode(stay, dst));
+                try!(<i32 as Protocol>::proto_encode(fade_out, dst));
+            }
+            TitleAction::Hide => {
+                try!(<Var<i32> as Protocol>::proto_encode(&3, dst));
+            }
+            TitleAction::Reset => {
+                try!(<Var<i32> as Protocol>::proto_encode(&4, dst));
+            }
+        }
+        Ok(())
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<TitleAction> {
+        match try!(<Var<i32> as Protocol>::proto_decode(src)) {
+            0 => Ok(TitleAction::SetTitle(try!(<Chat as Protocol>::proto_decode(src)))),
+            1 => Ok(TitleAction::SetSubtitle(try!(<Chat as Protocol>::proto_decode(src)))),
+            2 => Ok(TitleAction::SetTimes {
+                fade_in: try!(<i32 as Protocol>::proto_decode(src)),
+                stay: try!(<i32 as Protocol>::proto_decode(src)),
+                fade_out: try!(<i32 as Protocol>::proto_decode(src))
+            }),
+            3 => Ok(TitleAction::Hide),
+            4 => Ok(TitleAction::Reset),
+            action => Err(Error::new(ErrorKind::InvalidEnumValue { name: "TitleAction", value: action as i64 }))
+        }
+    }
+}
+
+/// What a player did to an entity in a `UseEntity` packet, encoded as a VarInt type and, for
+/// `InteractAt`, the target position relative to the entity.
+#[derive(Debug, PartialEq)]
+pub enum EntityUseAction {
+    Interact,
+    Attack,
+    InteractAt([f32; 3])
+}
+
+impl Protocol for EntityUseAction {
+    type Clean = EntityUseAction;
+
+    fn proto_len(value: &EntityUseAction) -> usize {
+        match *value {
+            EntityUseAction::InteractAt(_) => 1 + 12,
+            _ => 1
+        }
+    }
+
+    fn proto_encode(value: &EntityUseAction, dst: &mut Write) -> Result<()> {
+        match *value {
+            EntityUseAction::Interact => {
+                try!(<Var<i32> as Protocol>::proto_encode(&0, dst));
+            }
+            EntityUseAction::Attack => {
+                try!(<Var<i32> as Protocol>::proto_encode(&1, dst));
+            }
+            EntityUseAction::InteractAt(ref target) => {
+                try!(<Var<i32> as Protocol>::proto_encode(&2, dst));
+                try!(<[f32; 3] as Protocol>::proto_encode(target, dst));
+            }
+        }
+        Ok(())
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<EntityUseAction> {
+        match try!(<Var<i32> as Protocol>::proto_decode(src)) {
+            0 => Ok(EntityUseAction::Interact),
+            1 => Ok(EntityUseAction::Attack),
+            2 => Ok(EntityUseAction::InteractAt(try!(<[f32; 3] as Protocol>::proto_decode(src)))),
+            use_type => Err(Error::new(ErrorKind::InvalidEnumValue { name: "EntityUseAction", value: use_type as i64 }))
+        }
+    }
 }
 
 packets! {
     Handshaking => handshake {
-        clientbound {
+        clientbound {}
+        serverbound {
             0x00 => Handshake { proto_version: Var<i32>, server_address: String, server_port: u16, next_state: State }
         }
-        serverbound {}
     }
     Play => play {
         clientbound {
             0x00 => KeepAlive { keep_alive_id: Var<i32> }
             0x01 => JoinGame { entity_id: i32, gamemode: u8, dimension: Dimension, difficulty: u8, max_players: u8, level_type: String, reduced_debug_info: bool }
-            // 0x02 => ChatMessage { data: Chat, position: i8 }
+            0x02 => ChatMessage { data: Chat, position: i8 }
             0x03 => TimeUpdate { world_age: i64, time_of_day: i64 }
             0x04 => EntityEquipment { entity_id: Var<i32>, slot: i16, item: Option<Slot> }
             0x05 => WorldSpawn { location: BlockPos }
@@ -406,87 +1263,245 @@ packets! {
             0x09 => HeldItemChange { slot: i8 }
             0x0a => UseBed { entity_id: Var<i32>, location: BlockPos }
             0x0b => Animation { entity_id: Var<i32>, animation: u8 }
-            // 0x0c => SpawnPlayer { entity_id: Var<i32>, player_uuid: Uuid, position: [i32; 3], yaw: u8, pitch: u8, current_item: i16, metadata: Metadata }
+            0x0c => SpawnPlayer { entity_id: Var<i32>, player_uuid: Uuid, position: [Fixed<i32>; 3], yaw: Angle, pitch: Angle, current_item: i16, metadata: Metadata }
             0x0d => CollectItem { collected_eid: Var<i32>, collector_eid: Var<i32> }
-            // 0x0e => SpawnObject { entity_id: Var<i32>, type_: i8, position: [i32; 3], pitch: u8, yaw: u8, data: ObjectData }
-            // 0x0f => SpawnMob { entity_id: Var<i32>, type_: u8, position: [i32; 3], yaw: u8, pitch: u8, head_pitch: u8, velocity: [i16; 3], metadata: Metadata }
+            0x0e => SpawnObject { entity_id: Var<i32>, type_: i8, position: [Fixed<i32>; 3], pitch: Angle, yaw: Angle, data: ObjectData }
+            0x0f => SpawnMob { entity_id: Var<i32>, type_: u8, position: [Fixed<i32>; 3], yaw: Angle, pitch: Angle, head_pitch: Angle, velocity: [i16; 3], metadata: Metadata }
             0x10 => SpawnPainting { entity_id: Var<i32>, title: String, location: BlockPos, direction: u8 }
-            0x11 => SpawnExperienceOrb { entity_id: Var<i32>, position: [i32; 3], count: i16 }
+            0x11 => SpawnExperienceOrb { entity_id: Var<i32>, position: [Fixed<i32>; 3], count: i16 }
             0x12 => EntityVelocity { entity_id: Var<i32>, velocity: [i16; 3] }
             0x13 => DestroyEntities { entity_ids: Arr<Var<i32>, Var<i32>> }
             0x14 => EntityIdle { entity_id: Var<i32> }
-            0x15 => EntityRelativeMove { entity_id: Var<i32>, delta: [i8; 3], on_ground: bool }
-            0x16 => EntityLook { entity_id: Var<i32>, yaw: u8, pitch: u8, on_ground: bool }
-            0x17 => EntityLookAndRelativeMove { entity_id: Var<i32>, delta: [i8; 3], yaw: u8, pitch: u8, on_ground: bool }
-            0x18 => EntityTeleport { entity_id: Var<i32>, position: [i32; 3], yaw: u8, pitch: u8, on_ground: bool }
-            0x19 => EntityHeadLook { entity_id: Var<i32>, head_yaw: u8 }
+            0x15 => EntityRelativeMove { entity_id: Var<i32>, delta: [Fixed<i8>; 3], on_ground: bool }
+            0x16 => EntityLook { entity_id: Var<i32>, yaw: Angle, pitch: Angle, on_ground: bool }
+            0x17 => EntityLookAndRelativeMove { entity_id: Var<i32>, delta: [Fixed<i8>; 3], yaw: Angle, pitch: Angle, on_ground: bool }
+            0x18 => EntityTeleport { entity_id: Var<i32>, position: [Fixed<i32>; 3], yaw: Angle, pitch: Angle, on_ground: bool }
+            0x19 => EntityHeadLook { entity_id: Var<i32>, head_yaw: Angle }
             0x1A => EntityStatus { entity_id: i32, entity_status: i8 }
             0x1B => AttachEntity { riding_eid: i32, vehicle_eid: i32, leash: bool }
-            // 0x1C => EntityMetadata { entity_id: Var<i32>, metadata: Metadata }
+            0x1C => EntityMetadata { entity_id: Var<i32>, metadata: Metadata }
             0x1D => EntityEffect { entity_id: Var<i32>, effect_id: i8, amplifier: i8, duration: Var<i32>, hide_particles: bool }
             0x1E => RemoveEntityEffect { entity_id: Var<i32>, effect_id: i8 }
             0x1F => SetExperience { xp_bar: f32, level: Var<i32>, xp_total: Var<i32> }
-            // 0x20 => EntityProperties { entity_id: Var<i32>, properties: Arr<i32, Property> }
+            0x20 => EntityProperties { entity_id: Var<i32>, properties: Arr<i32, Property> }
             0x21 => ChunkData { x: i32, z: i32, continuous: bool, mask: u16, chunk_data: Arr<Var<i32>, u8> }
             0x22 => MultiBlockChange { chunk_x: i32, chunk_z: i32, records: Arr<Var<i32>, BlockChangeRecord> }
             0x23 => BlockChange { location: BlockPos, block_id: Var<i32> }
             0x24 => BlockAction { location: BlockPos, byte1: u8, byte2: u8, block_type: Var<i32> }
             0x25 => BlockBreakAnimation { entity_id: Var<i32>, location: BlockPos, destroy_stage: i8 }
-            // 0x26 => MapChunkBulk { sky_light_sent: bool, chunks: Vec<Chunk>; impl Packet for MapChunkBulk { ... } } // PROBLEM: chunks is encoded as two arrays, the first one specifying which sections of each chunk column are empty
+            0x26 => MapChunkBulk { sky_light_sent: bool, columns: Vec<(ChunkMeta, ChunkColumn)>;
+                impl Packet for MapChunkBulk {
+                    fn encode(&self, mut dst: &mut Write) -> Result<()> {
+                        use std::num::Int;
+
+                        try!(<bool as Protocol>::proto_encode(&self.sky_light_sent, dst).map_err(|err| err.in_field("sky_light_sent")));
+                        try!(<Var<i32> as Protocol>::proto_encode(&(self.columns.len() as i32), dst).map_err(|err| err.in_field("columns")));
+                        for &(ref meta, ref column) in self.columns.iter() {
+                            // All columns are sent in full, with the same kind of lighting.
+                            if column.biomes.is_none() || meta.mask.count_ones() as usize != column.chunks.len() || column.chunks.iter().any(|chunk| chunk.sky_light.is_some() != self.sky_light_sent) {
+                                return Err(Error::invalid("invalid chunk column", Some(format!("column at {}, {} does not match its mask or sky light flag, or has no biomes", meta.x, meta.z))).in_field("columns"));
+                            }
+                            try!(<ChunkMeta as Protocol>::proto_encode(meta, dst).map_err(|err| err.in_field("columns")));
+                        }
+                        for &(_, ref column) in self.columns.iter() {
+                            let data = try!(column.encode().map_err(|err| err.in_field("columns")));
+                            try!(dst.write_all(&data).map_err(|err| Error::from_error(err).in_field("columns")));
+                        }
+                        Ok(())
+                    }
+                    #[allow(unused_variables)]
+                    fn decode(mut src: &mut Read, len: usize) -> Result<MapChunkBulk> {
+                        use std::num::Int;
+
+                        let sky_light_sent = try!(decode_field::<bool>("MapChunkBulk", "sky_light_sent", src));
+                        let num_columns = try!(decode_field::<Var<i32>>("MapChunkBulk", "columns", src));
+                        try!(limits::check("array length", num_columns as usize, limits::current().max_array_len).map_err(|err| err.in_field("columns")));
+                        let mut metas = Vec::new();
+                        for _ in 0..num_columns {
+                            metas.push(try!(decode_field::<ChunkMeta>("MapChunkBulk", "columns", src)));
+                        }
+                        let (dimension, section_len) = if sky_light_sent {
+                            (Dimension::Overworld, 8192 + 2048 + 2048)
+                        } else {
+                            (Dimension::Nether, 8192 + 2048)
+                        };
+                        let mut columns = Vec::new();
+                        for meta in metas.into_iter() {
+                            let data_len = meta.mask.count_ones() as usize * section_len + 256; // sections + biomes
+                            let chunk_data = ChunkData {
+                                x: meta.x,
+                                z: meta.z,
+                                continuous: true,
+                                mask: meta.mask,
+                                chunk_data: try!(src.read_exact(data_len).map_err(|err| err.in_field("columns")))
+                            };
+                            columns.push((meta, try!(ChunkColumn::decode(chunk_data, dimension).map_err(|err| err.in_field("columns")))));
+                        }
+                        Ok(MapChunkBulk {
+                            sky_light_sent: sky_light_sent,
+                            columns: columns
+                        })
+                    }
+                }
+            }
             0x27 => Explosion { position: [f32; 3], radius: f32, records: Arr<i32, [i8; 3]>, player_motion: [f32; 3] }
             0x28 => Effect { effect_id: i32, location: BlockPos, data: i32, disable_relative_volume: bool }
             0x29 => SoundEffect { name: String, position: [i32; 3], volume: f32, pitch: u8 }
-            // 0x2a => Particle { particle_id: i32, long_distance: bool, position: [f32; 3], offset: [f32; 3], particle_data: f32, particle_count: i32, data: Vec<i32>; impl Packet for Particle { ... } } // PROBLEM: length of data depends on particle_id
+            0x2a => Particle { particle: ParticleType, long_distance: bool, position: [f32; 3], offset: [f32; 3], particle_data: f32, particle_count: i32;
+                impl Packet for Particle {
+                    fn encode(&self, mut dst: &mut Write) -> Result<()> {
+                        try!(<i32 as Protocol>::proto_encode(&self.particle.id(), dst).map_err(|err| err.in_field("particle")));
+                        try!(<bool as Protocol>::proto_encode(&self.long_distance, dst).map_err(|err| err.in_field("long_distance")));
+                        try!(<[f32; 3] as Protocol>::proto_encode(&self.position, dst).map_err(|err| err.in_field("position")));
+                        try!(<[f32; 3] as Protocol>::proto_encode(&self.offset, dst).map_err(|err| err.in_field("offset")));
+                        try!(<f32 as Protocol>::proto_encode(&self.particle_data, dst).map_err(|err| err.in_field("particle_data")));
+                        try!(<i32 as Protocol>::proto_encode(&self.particle_count, dst).map_err(|err| err.in_field("particle_count")));
+                        self.particle.encode_data(dst).map_err(|err| err.in_field("particle"))
+                    }
+                    #[allow(unused_variables)]
+                    fn decode(mut src: &mut Read, len: usize) -> Result<Particle> {
+                        let id = try!(decode_field::<i32>("Particle", "particle", src));
+                        let long_distance = try!(decode_field::<bool>("Particle", "long_distance", src));
+                        let position = try!(decode_field::<[f32; 3]>("Particle", "position", src));
+                        let offset = try!(decode_field::<[f32; 3]>("Particle", "offset", src));
+                        let particle_data = try!(decode_field::<f32>("Particle", "particle_data", src));
+                        let particle_count = try!(decode_field::<i32>("Particle", "particle_count", src));
+                        Ok(Particle {
+                            particle: try!(ParticleType::decode_data(id, src).map_err(|err| err.in_field("particle"))),
+                            long_distance: long_distance,
+                            position: position,
+                            offset: offset,
+                            particle_data: particle_data,
+                            particle_count: particle_count
+                        })
+                    }
+                }
+            }
             0x2b => ChangeGameState { reason: u8, value: f32 }
-            0x2c => SpawnGlobalEntity { entity_id: Var<i32>, type_: i8, position: [i32; 3] }
-            // 0x2d => OpenWindow { window_id: u8, window_type: String, window_title: Chat, slots: u8, entity_id: Option<i32>; impl Packet for OpenWindow { ... } } // PROBLEM: entity_id depends on window_type
+            0x2c => SpawnGlobalEntity { entity_id: Var<i32>, type_: i8, position: [Fixed<i32>; 3] }
+            0x2d => OpenWindow { window_id: u8, window_type: WindowType, window_title: Chat, slots: u8;
+                impl Packet for OpenWindow {
+                    fn encode(&self, mut dst: &mut Write) -> Result<()> {
+                        try!(<u8 as Protocol>::proto_encode(&self.window_id, dst).map_err(|err| err.in_field("window_id")));
+                        try!(<String as Protocol>::proto_encode(&self.window_type.name().to_string(), dst).map_err(|err| err.in_field("window_type")));
+                        try!(<Chat as Protocol>::proto_encode(&self.window_title, dst).map_err(|err| err.in_field("window_title")));
+                        try!(<u8 as Protocol>::proto_encode(&self.slots, dst).map_err(|err| err.in_field("slots")));
+                        if let WindowType::EntityHorse(entity_id) = self.window_type {
+                            try!(<i32 as Protocol>::proto_encode(&entity_id, dst).map_err(|err| err.in_field("window_type")));
+                        }
+                        Ok(())
+                    }
+                    #[allow(unused_variables)]
+                    fn decode(mut src: &mut Read, len: usize) -> Result<OpenWindow> {
+                        let window_id = try!(decode_field::<u8>("OpenWindow", "window_id", src));
+                        let name = try!(decode_field::<String>("OpenWindow", "window_type", src));
+                        let window_title = try!(decode_field::<Chat>("OpenWindow", "window_title", src));
+                        let slots = try!(decode_field::<u8>("OpenWindow", "slots", src));
+                        let entity_id = if name == "EntityHorse" {
+                            try!(decode_field::<i32>("OpenWindow", "window_type", src))
+                        } else {
+                            0
+                        };
+                        let window_type = try!(WindowType::from_name(&name, entity_id).ok_or(Error::invalid("invalid window type", Some(format!("unknown window type {}", name))).in_field("window_type")));
+                        Ok(OpenWindow {
+                            window_id: window_id,
+                            window_type: window_type,
+                            window_title: window_title,
+                            slots: slots
+                        })
+                    }
+                }
+            }
             0x2e => CloseWindow { window_id: u8 }
             0x2f => SetSlot { window_id: u8, slot: i16, data: Option<Slot> }
             0x30 => WindowItems { window_id: u8, slots: Arr<i16, Option<Slot>> }
             0x31 => WindowProperty { window_id: u8, property: i16, value: i16 }
             0x32 => ConfirmTransaction { window_id: u8, action_number: i16, accepted: bool }
-            // 0x33 => UpdateSign { location: BlockPos, line0: Chat, line1: Chat, line2: Chat, line3: Chat }
-            // 0x34 => UpdateMap { map_id: Var<i32>, scale: i8, icons: Arr<Var<i32>, MapIcon>, data: MapData } // MapData is a quirky format holding optional pixel data for an arbitrary rectangle on the map
-            // 0x35 => UpdateBlockEntity { location: [i32; 3], action: u8, nbt_data: Nbt; impl Packet for UpdateBlockEntity { ... } } // PROBLEM: nbt_data is omitted entirely if it encodes an empty NBT tag
+            0x33 => UpdateSign { location: BlockPos, line0: Chat, line1: Chat, line2: Chat, line3: Chat }
+            0x34 => UpdateMap { map_id: Var<i32>, scale: i8, icons: Arr<Var<i32>, MapIcon>, data: OptionalMapData }
+            0x35 => UpdateBlockEntity { location: BlockPos, action: BlockEntityAction, nbt_data: OptionalNbt }
             0x36 => SignEditorOpen { location: BlockPos }
             0x37 => Statistics { stats: Arr<Var<i32>, Stat> }
-            // 0x38 => UpdatePlayerList { action: Var<i32>, players: Arr<Var<i32>, PlayerListItem>; impl Packet for UpdatePlayerList { ... } } // PROBLEM: suructure of `players` elements depends on `action`
+            0x38 => UpdatePlayerList { action: PlayerListAction;
+                impl Packet for UpdatePlayerList {
+                    fn encode(&self, mut dst: &mut Write) -> Result<()> {
+                        let result = match self.action {
+                            PlayerListAction::AddPlayer(ref players) => {
+                                try!(<Var<i32> as Protocol>::proto_encode(&0, dst));
+                                <Arr<Var<i32>, PlayerInfo> as Protocol>::proto_encode(players, dst)
+                            }
+                            PlayerListAction::UpdateGamemode(ref players) => {
+                                try!(<Var<i32> as Protocol>::proto_encode(&1, dst));
+                                <Arr<Var<i32>, PlayerGamemode> as Protocol>::proto_encode(players, dst)
+                            }
+                            PlayerListAction::UpdateLatency(ref players) => {
+                                try!(<Var<i32> as Protocol>::proto_encode(&2, dst));
+                                <Arr<Var<i32>, PlayerLatency> as Protocol>::proto_encode(players, dst)
+                            }
+                            PlayerListAction::UpdateDisplayName(ref players) => {
+                                try!(<Var<i32> as Protocol>::proto_encode(&3, dst));
+                                <Arr<Var<i32>, PlayerDisplayName> as Protocol>::proto_encode(players, dst)
+                            }
+                            PlayerListAction::RemovePlayer(ref players) => {
+                                try!(<Var<i32> as Protocol>::proto_encode(&4, dst));
+                                <Arr<Var<i32>, Uuid> as Protocol>::proto_encode(players, dst)
+                            }
+                        };
+                        result.map_err(|err| err.in_field("action"))
+                    }
+                    #[allow(unused_variables)]
+                    fn decode(mut src: &mut Read, len: usize) -> Result<UpdatePlayerList> {
+                        let action = match try!(decode_field::<Var<i32>>("UpdatePlayerList", "action", src)) {
+                            0 => PlayerListAction::AddPlayer(try!(decode_field::<Arr<Var<i32>, PlayerInfo>>("UpdatePlayerList", "action", src))),
+                            1 => PlayerListAction::UpdateGamemode(try!(decode_field::<Arr<Var<i32>, PlayerGamemode>>("UpdatePlayerList", "action", src))),
+                            2 => PlayerListAction::UpdateLatency(try!(decode_field::<Arr<Var<i32>, PlayerLatency>>("UpdatePlayerList", "action", src))),
+                            3 => PlayerListAction::UpdateDisplayName(try!(decode_field::<Arr<Var<i32>, PlayerDisplayName>>("UpdatePlayerList", "action", src))),
+                            4 => PlayerListAction::RemovePlayer(try!(decode_field::<Arr<Var<i32>, Uuid>>("UpdatePlayerList", "action", src))),
+                            action => return Err(Error::new(ErrorKind::InvalidEnumValue { name: "PlayerListAction", value: action as i64 }).in_field("action"))
+                        };
+                        Ok(UpdatePlayerList { action: action })
+                    }
+                }
+            }
             0x39 => PlayerAbilities { flags: i8, flying_speed: f32, walking_speed: f32 }
             0x3a => TabComplete { matches: Arr<Var<i32>, String> }
-            // 0x3b => ScoreboardObjective { objective_name: String, mode: ObjectiveAction }
-            // 0x3c => UpdateScore { score_name: String, action: ScoreAction }
+            0x3b => ScoreboardObjective { objective_name: String, mode: ObjectiveAction }
+            0x3c => UpdateScore { score_name: String, action: ScoreAction }
             0x3d => DisplayScoreboard { position: i8, score_name: String }
-            // 0x3e => UpdateTeam { team_name: String, action: TeamAction }
+            0x3e => UpdateTeam { team_name: String, action: TeamAction }
             0x3f => PluginMessage { channel: String, data: Vec<u8>;
                 impl Packet for PluginMessage {
-                    fn encode(&self, mut dst: &mut Write) -> io::Result<()> {
-                        try!(<String as Protocol>::proto_encode(&self.channel, dst));
-                        try!(dst.write_all(&self.data));
+                    fn encode(&self, mut dst: &mut Write) -> Result<()> {
+                        try!(<String as Protocol>::proto_encode(&self.channel, dst).map_err(|err| err.in_field("channel")));
+                        try!(dst.write_all(&self.data).map_err(|err| Error::from_error(err).in_field("data")));
                         Ok(())
                     }
-                    fn decode(mut src: &mut Read, len: usize) -> io::Result<PluginMessage> {
-                        Ok(PluginMessage{
-                            channel: try!(<String as Protocol>::proto_decode(src)),
-                            data: try!(src.read_exact(len)),
+                    fn decode(mut src: &mut Read, len: usize) -> Result<PluginMessage> {
+                        let channel = try!(decode_field::<String>("PluginMessage", "channel", src));
+                        // The data takes up the rest of the body.
+                        let data_len = len - <String as Protocol>::proto_len(&channel);
+                        Ok(PluginMessage {
+                            channel: channel,
+                            data: try!(src.read_exact(data_len).map_err(|err| err.in_field("data")))
                         })
                     }
                 }
             }
-            // 0x40 => Disconnect { reason: Chat }
+            0x40 => Disconnect { reason: Chat }
             0x41 => ServerDifficulty { difficulty: u8 }
-            // 0x42 => PlayCombatEvent { event: CombatEvent }
+            0x42 => PlayCombatEvent { event: CombatEvent }
             0x43 => Camera { camera_id: Var<i32> }
-            // 0x44 => WorldBorder { action: WorldBorderAction }
-            // 0x45 => Title { action: TitleAction }
+            0x44 => WorldBorder { action: WorldBorderAction }
+            0x45 => Title { action: TitleAction }
             0x46 => SetCompression { threshold: Var<i32> }
-            // 0x47 => PlayerListHeaderFooter { header: Chat, footer: Chat }
+            0x47 => PlayerListHeaderFooter { header: Chat, footer: Chat }
             0x48 => ResourcePackSend { url: String, hash: String }
             0x49 => UpdateEntityNbt { entity_id: Var<i32>, tag: NbtBlob }
         }
         serverbound {
             0x00 => KeepAlive { keep_alive_id: i32 }
             0x01 => ChatMessage { message: String }
-            // 0x02 => UseEntity { target_eid: i32, use_type: EntityUseAction }
+            0x02 => UseEntity { target_eid: Var<i32>, use_type: EntityUseAction }
             0x03 => PlayerIdle { on_ground: bool }
             0x04 => PlayerPosition { position: [f64; 3], on_ground: bool }
             0x05 => PlayerLook { yaw: f32, pitch: f32, on_ground: bool }
@@ -502,22 +1517,25 @@ packets! {
             0x0f => ConfirmTransaction { window_id: u8, action_number: i16, accepted: bool }
             0x10 => CreativeInventoryAction { slot: i16, clicked_item: Option<Slot> }
             0x11 => EnchantItem { window_id: u8, enchantment: i8 }
-            // 0x12 => UpdateSign { location: BlockPos, line0: Chat, line1: Chat, line2: Chat, line3: Chat }
+            0x12 => UpdateSign { location: BlockPos, line0: Chat, line1: Chat, line2: Chat, line3: Chat }
             0x13 => PlayerAbilities { flags: i8, flying_speed: f32, walking_speed: f32 }
             0x14 => TabComplete { text: String, looking_at: Option<i64> }
             0x15 => ClientSettings { locale: String, view_distance: i8, chat_mode: i8, chat_colors: bool, displayed_skin_parts: u8 }
             0x16 => ClientStatus { action_id: Var<i32> }
             0x17 => PluginMessage { channel: String, data: Vec<u8>;
                 impl Packet for PluginMessage {
-                    fn encode(&self, mut dst: &mut Write) -> io::Result<()> {
-                        try!(<String as Protocol>::proto_encode(&self.channel, dst));
-                        try!(dst.write_all(&self.data));
+                    fn encode(&self, mut dst: &mut Write) -> Result<()> {
+                        try!(<String as Protocol>::proto_encode(&self.channel, dst).map_err(|err| err.in_field("channel")));
+                        try!(dst.write_all(&self.data).map_err(|err| Error::from_error(err).in_field("data")));
                         Ok(())
                     }
-                    fn decode(mut src: &mut Read, len: usize) -> io::Result<PluginMessage> {
-                        Ok(PluginMessage{
-                            channel: try!(<String as Protocol>::proto_decode(src)),
-                            data: try!(src.read_exact(len)),
+                    fn decode(mut src: &mut Read, len: usize) -> Result<PluginMessage> {
+                        let channel = try!(decode_field::<String>("PluginMessage", "channel", src));
+                        // The data takes up the rest of the body.
+                        let data_len = len - <String as Protocol>::proto_len(&channel);
+                        Ok(PluginMessage {
+                            channel: channel,
+                            data: try!(src.read_exact(data_len).map_err(|err| err.in_field("data")))
                         })
                     }
                 }
@@ -538,9 +1556,9 @@ packets! {
     }
     Login => login {
         clientbound {
-            // 0x00 => Disconnect { reason: Chat }
+            0x00 => Disconnect { reason: Chat }
             0x01 => EncryptionRequest { server_id: String, pubkey: Arr<Var<i32>, u8>, verify_token: Arr<Var<i32>, u8> }
-            // 0x02 => LoginSuccess { uuid: Uuid, username: String; impl Packet for LoginSuccess { ... } } // NOTE: uuid field is encoded as a string!
+            0x02 => LoginSuccess { uuid: UuidString, username: String }
             0x03 => SetCompression { threshold: Var<i32> }
         }
         serverbound {
@@ -549,3 +1567,572 @@ packets! {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+
+    use std::io;
+    use std::io::prelude::*;
+    use std::iter;
+
+    use std::default::Default;
+
+    use error::ErrorKind;
+    use limits::{self, LimitConfig, Limits};
+    use types::consts::{ModifierOperation, MOVEMENT_SPEED};
+    use types::{Chat, Chunk, ChunkColumn, WindowType};
+    use uuid::Uuid;
+
+    use packet::{ChunkMeta, CombatEvent, Direction, EntityUseAction, Modifier, ObjectData, ObjectiveAction, ObjectiveType, PACKETS, Packet, PacketEnum, PlayerInfo, PlayerListAction, PlayerProperty, Property, Protocol, ScoreAction, State, TeamAction, TeamInfo, TitleAction, WorldBorderAction, decode_packet, inflate_frame, packet_info, read_packet};
+    use packet::play;
+    use packet::play::clientbound::{EntityProperties, MapChunkBulk, OpenWindow, UpdatePlayerList};
+    use packet::status;
+    use packet::status::clientbound::{Pong, StatusResponse};
+
+    #[test]
+    fn packet_write_uncompressed() {
+        let mut dst = Vec::new();
+        Pong { time: 1 }.write(&mut dst, None).unwrap();
+        let bytes = vec![0x09, 0x01, 0, 0, 0, 0, 0, 0, 0, 1];
+        assert_eq!(&dst, &bytes);
+    }
+
+    #[test]
+    fn packet_write_below_threshold() {
+        let mut dst = Vec::new();
+        Pong { time: 1 }.write(&mut dst, Some(256)).unwrap();
+        let bytes = vec![0x0a, 0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 1];
+        assert_eq!(&dst, &bytes);
+    }
+
+    #[test]
+    fn packet_compressed_roundtrip() {
+        let response: String = iter::repeat('x').take(1000).collect();
+        let mut dst = Vec::new();
+        StatusResponse { response: response.clone() }.write(&mut dst, Some(256)).unwrap();
+        assert!(dst.len() < 1000);
+        let mut src = io::Cursor::new(dst);
+        match read_packet(Direction::Clientbound, State::Status, Some(256), &mut src).unwrap() {
+            PacketEnum::Status(status::PacketEnum::Clientbound(status::clientbound::PacketEnum::StatusResponse(packet))) => {
+                assert_eq!(packet.response, response);
+            }
+            _ => panic!("decoded the wrong packet")
+        }
+    }
+
+    /// The contents of a compressed frame with a single-byte data length.
+    fn deflated(data_len: u8, data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(vec![data_len], Compression::Default);
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn inflate_frame_checks() {
+        let pong = [0x01, 0, 0, 0, 0, 0, 0, 0, 1];
+        assert_eq!(inflate_frame(deflated(9, &pong), Some(8)).unwrap(), pong.to_vec());
+        // Packets below the threshold must be sent uncompressed.
+        assert!(inflate_frame(deflated(9, &pong), Some(256)).is_err());
+        // Compressed data past the data length is not dropped silently.
+        assert!(inflate_frame(deflated(9, &[0x01, 0, 0, 0, 0, 0, 0, 0, 1, 2]), Some(8)).is_err());
+    }
+
+    #[test]
+    fn plugin_message_decode() {
+        // Channel "MC|" followed by two bytes of data.
+        match decode_packet(Direction::Clientbound, State::Play, &[0x3f, 3, 0x4d, 0x43, 0x7c, 1, 2]).unwrap() {
+            PacketEnum::Play(play::PacketEnum::Clientbound(play::clientbound::PacketEnum::PluginMessage(packet))) => {
+                assert_eq!(packet.channel, "MC|");
+                assert_eq!(packet.data, vec![1, 2]);
+            }
+            _ => panic!("decoded the wrong packet")
+        }
+        match decode_packet(Direction::Serverbound, State::Play, &[0x17, 3, 0x4d, 0x43, 0x7c, 1, 2]).unwrap() {
+            PacketEnum::Play(play::PacketEnum::Serverbound(play::serverbound::PacketEnum::PluginMessage(packet))) => {
+                assert_eq!(packet.channel, "MC|");
+                assert_eq!(packet.data, vec![1, 2]);
+            }
+            _ => panic!("decoded the wrong packet")
+        }
+    }
+
+    #[test]
+    fn packet_decode_exact() {
+        let frame = vec![0x01, 0, 0, 0, 0, 0, 0, 0, 1];
+        match decode_packet(Direction::Clientbound, State::Status, &frame).unwrap() {
+            PacketEnum::Status(status::PacketEnum::Clientbound(status::clientbound::PacketEnum::Pong(packet))) => {
+                assert_eq!(packet.time, 1);
+            }
+            _ => panic!("decoded the wrong packet")
+        }
+    }
+
+    #[test]
+    fn packet_decode_leftover_bytes() {
+        let frame = vec![0x01, 0, 0, 0, 0, 0, 0, 0, 1, 0xff];
+        assert!(decode_packet(Direction::Clientbound, State::Status, &frame).is_err());
+    }
+
+    #[test]
+    fn packet_decode_past_frame() {
+        let frame = vec![0x01, 0, 0, 0, 0, 0, 0, 0];
+        assert!(decode_packet(Direction::Clientbound, State::Status, &frame).is_err());
+    }
+
+    #[test]
+    fn object_data_without_velocity() {
+        let value = ObjectData { data: 0, velocity: None };
+        let mut dst = Vec::new();
+        <ObjectData as Protocol>::proto_encode(&value, &mut dst).unwrap();
+        assert_eq!(&dst, &vec![0, 0, 0, 0]);
+        let value = <ObjectData as Protocol>::proto_decode(&mut io::Cursor::new(dst)).unwrap();
+        assert!(value.velocity.is_none());
+    }
+
+    #[test]
+    fn object_data_mismatched_velocity() {
+        let mut dst = Vec::new();
+        assert!(<ObjectData as Protocol>::proto_encode(&ObjectData { data: 1, velocity: None }, &mut dst).is_err());
+        assert!(<ObjectData as Protocol>::proto_encode(&ObjectData { data: 0, velocity: Some([0, 0, 0]) }, &mut dst).is_err());
+        assert!(dst.is_empty());
+    }
+
+    #[test]
+    fn object_data_with_velocity() {
+        let value = ObjectData { data: 1, velocity: Some([1, -1, 0]) };
+        let bytes = vec![0, 0, 0, 1, 0, 1, 0xff, 0xff, 0, 0];
+        let mut dst = Vec::new();
+        <ObjectData as Protocol>::proto_encode(&value, &mut dst).unwrap();
+        assert_eq!(&dst, &bytes);
+        assert_eq!(<ObjectData as Protocol>::proto_len(&value), bytes.len());
+        let value = <ObjectData as Protocol>::proto_decode(&mut io::Cursor::new(bytes)).unwrap();
+        assert_eq!(value.velocity, Some([1, -1, 0]));
+    }
+
+    #[test]
+    fn map_chunk_bulk_roundtrip() {
+        let column = ChunkColumn {
+            chunks: vec![Chunk::new(1, 0xff), Chunk::new(2, 0xff)],
+            biomes: Some([1; 256])
+        };
+        let packet = MapChunkBulk {
+            sky_light_sent: true,
+            columns: vec![(ChunkMeta { x: 3, z: -4, mask: 0x0005 }, column)]
+        };
+        let mut dst = Vec::new();
+        packet.encode(&mut dst).unwrap();
+        assert_eq!(dst.len(), 1 + 1 + 10 + 2 * (8192 + 2048 + 2048) + 256);
+
+        let len = dst.len();
+        let packet = <MapChunkBulk as Packet>::decode(&mut io::Cursor::new(dst), len).unwrap();
+        assert!(packet.sky_light_sent);
+        assert_eq!(packet.columns.len(), 1);
+        let (ref meta, ref column) = packet.columns[0];
+        assert_eq!((meta.x, meta.z, meta.mask), (3, -4, 0x0005));
+        assert_eq!(column.chunks.len(), 2);
+        assert_eq!(column.chunks[1].blocks[0], 2);
+        assert_eq!(column.biomes.unwrap()[0], 1);
+    }
+
+    #[test]
+    fn map_chunk_bulk_missing_biomes() {
+        let column = ChunkColumn {
+            chunks: vec![Chunk::new(1, 0xff)],
+            biomes: None
+        };
+        let packet = MapChunkBulk {
+            sky_light_sent: true,
+            columns: vec![(ChunkMeta { x: 0, z: 0, mask: 0x0001 }, column)]
+        };
+        assert!(packet.encode(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn map_chunk_bulk_too_many_columns() {
+        let _limits = limits::scope(Limits { max_array_len: 2, ..Default::default() });
+        let err = <MapChunkBulk as Packet>::decode(&mut io::Cursor::new(vec![1, 3]), 2).err().unwrap();
+        assert_eq!(err.location(), "columns");
+        match err.kind {
+            ErrorKind::LimitExceeded { limit: "array length", max: 2 } => {}
+            kind => panic!("unexpected error {:?}", kind)
+        }
+    }
+
+    #[test]
+    fn open_window_horse() {
+        let packet = OpenWindow {
+            window_id: 1,
+            window_type: WindowType::EntityHorse(42),
+            window_title: Chat::text("Horse"),
+            slots: 2
+        };
+        let mut dst = Vec::new();
+        packet.encode(&mut dst).unwrap();
+        assert_eq!(&dst[dst.len() - 4..], &[0, 0, 0, 42]);
+
+        let len = dst.len();
+        let packet = <OpenWindow as Packet>::decode(&mut io::Cursor::new(dst), len).unwrap();
+        assert_eq!(packet.window_type, WindowType::EntityHorse(42));
+        assert_eq!(packet.slots, 2);
+    }
+
+    #[test]
+    fn open_window_chest() {
+        let packet = OpenWindow {
+            window_id: 1,
+            window_type: WindowType::Chest,
+            window_title: Chat::text("Chest"),
+            slots: 27
+        };
+        let mut dst = Vec::new();
+        packet.encode(&mut dst).unwrap();
+        assert_eq!(dst[dst.len() - 1], 27);
+
+        let len = dst.len();
+        let packet = <OpenWindow as Packet>::decode(&mut io::Cursor::new(dst), len).unwrap();
+        assert_eq!(packet.window_type, WindowType::Chest);
+    }
+
+    #[test]
+    fn update_player_list_add() {
+        let player = PlayerInfo {
+            uuid: Uuid::nil(),
+            name: "Herobrine".to_string(),
+            properties: vec![PlayerProperty { name: "textures".to_string(), value: "e30=".to_string(), signature: None }],
+            gamemode: 1,
+            ping: 42,
+            display_name: Some(Chat::text("Herobrine"))
+        };
+        let packet = UpdatePlayerList { action: PlayerListAction::AddPlayer(vec![player]) };
+        let mut dst = Vec::new();
+        packet.encode(&mut dst).unwrap();
+        assert_eq!(&dst[..2], &[0, 1]);
+
+        let len = dst.len();
+        match <UpdatePlayerList as Packet>::decode(&mut io::Cursor::new(dst), len).unwrap().action {
+            PlayerListAction::AddPlayer(ref players) => {
+                assert_eq!(players.len(), 1);
+                assert_eq!(players[0].name, "Herobrine");
+                assert_eq!(players[0].properties[0].value, "e30=");
+                assert_eq!(players[0].ping, 42);
+                assert_eq!(players[0].display_name, Some(Chat::text("Herobrine")));
+            }
+            _ => panic!("decoded the wrong action")
+        }
+    }
+
+    #[test]
+    fn update_player_list_remove() {
+        let packet = UpdatePlayerList { action: PlayerListAction::RemovePlayer(vec![Uuid::nil()]) };
+        let mut dst = Vec::new();
+        packet.encode(&mut dst).unwrap();
+        let mut bytes = vec![4, 1];
+        bytes.extend([0u8; 16].iter().map(|&b| b));
+        assert_eq!(&dst, &bytes);
+    }
+
+    #[test]
+    fn update_player_list_bad_action() {
+        let bytes = vec![5, 0];
+        assert!(<UpdatePlayerList as Packet>::decode(&mut io::Cursor::new(bytes), 2).is_err());
+    }
+
+    #[test]
+    fn objective_action_create() {
+        let value = ObjectiveAction::Create { display_name: "Kills".to_string(), kind: ObjectiveType::Hearts };
+        let bytes = vec![0, 5, 0x4b, 0x69, 0x6c, 0x6c, 0x73, 6, 0x68, 0x65, 0x61, 0x72, 0x74, 0x73];
+        let mut dst = Vec::new();
+        <ObjectiveAction as Protocol>::proto_encode(&value, &mut dst).unwrap();
+        assert_eq!(&dst, &bytes);
+        assert_eq!(<ObjectiveAction as Protocol>::proto_len(&value), bytes.len());
+        match <ObjectiveAction as Protocol>::proto_decode(&mut io::Cursor::new(bytes)).unwrap() {
+            ObjectiveAction::Create { display_name, kind } => {
+                assert_eq!(display_name, "Kills");
+                assert_eq!(kind, ObjectiveType::Hearts);
+            }
+            _ => panic!("decoded the wrong mode")
+        }
+    }
+
+    #[test]
+    fn score_action_remove() {
+        let value = ScoreAction::Remove { objective: "a".to_string() };
+        let mut dst = Vec::new();
+        <ScoreAction as Protocol>::proto_encode(&value, &mut dst).unwrap();
+        assert_eq!(&dst, &vec![1, 1, 0x61]);
+        assert_eq!(<ScoreAction as Protocol>::proto_len(&value), 3);
+    }
+
+    #[test]
+    fn team_action_create() {
+        let info = TeamInfo {
+            display_name: "Red".to_string(),
+            prefix: "[R]".to_string(),
+            suffix: "".to_string(),
+            friendly_fire: 1,
+            name_tag_visibility: "always".to_string(),
+            color: 12
+        };
+        let value = TeamAction::Create { info: info, players: vec!["Herobrine".to_string()] };
+        let mut dst = Vec::new();
+        <TeamAction as Protocol>::proto_encode(&value, &mut dst).unwrap();
+        assert_eq!(<TeamAction as Protocol>::proto_len(&value), dst.len());
+        match <TeamAction as Protocol>::proto_decode(&mut io::Cursor::new(dst)).unwrap() {
+            TeamAction::Create { info, players } => {
+                assert_eq!(info.prefix, "[R]");
+                assert_eq!(info.color, 12);
+                assert_eq!(players, vec!["Herobrine".to_string()]);
+            }
+            _ => panic!("decoded the wrong mode")
+        }
+    }
+
+    #[test]
+    fn world_border_lerp_size() {
+        let value = WorldBorderAction::LerpSize { old_radius: 2.0, new_radius: 1.0, speed: 300 };
+        let mut dst = Vec::new();
+        <WorldBorderAction as Protocol>::proto_encode(&value, &mut dst).unwrap();
+        assert_eq!(&dst, &vec![1,
+                               0x40, 0, 0, 0, 0, 0, 0, 0,
+                               0x3f, 0xf0, 0, 0, 0, 0, 0, 0,
+                               0xac, 0x02]);
+        assert_eq!(<WorldBorderAction as Protocol>::proto_len(&value), dst.len());
+        match <WorldBorderAction as Protocol>::proto_decode(&mut io::Cursor::new(dst)).unwrap() {
+            WorldBorderAction::LerpSize { old_radius, new_radius, speed } => {
+                assert_eq!(old_radius, 2.0);
+                assert_eq!(new_radius, 1.0);
+                assert_eq!(speed, 300);
+            }
+            _ => panic!("decoded the wrong action")
+        }
+    }
+
+    #[test]
+    fn title_set_times() {
+        let value = TitleAction::SetTimes { fade_in: 10, stay: 70, fade_out: 20 };
+        let mut dst = Vec::new();
+        <TitleAction as Protocol>::proto_encode(&value, &mut dst).unwrap();
+        assert_eq!(&dst, &vec![2, 0, 0, 0, 10, 0, 0, 0, 70, 0, 0, 0, 20]);
+        assert_eq!(<TitleAction as Protocol>::proto_len(&value), dst.len());
+    }
+
+    #[test]
+    fn combat_event_entity_dead() {
+        let value = CombatEvent::EntityDead { player_id: 1, entity_id: -1, message: "x".to_string() };
+        let mut dst = Vec::new();
+        <CombatEvent as Protocol>::proto_encode(&value, &mut dst).unwrap();
+        assert_eq!(&dst, &vec![2, 1, 0xff, 0xff, 0xff, 0xff, 1, 0x78]);
+        assert_eq!(<CombatEvent as Protocol>::proto_len(&value), dst.len());
+    }
+
+    #[test]
+    fn bad_combat_event() {
+        assert!(<CombatEvent as Protocol>::proto_decode(&mut io::Cursor::new(vec![3])).is_err());
+    }
+
+    #[test]
+    fn property_with_modifier() {
+        let value = Property {
+            key: MOVEMENT_SPEED.to_string(),
+            value: 0.1,
+            modifiers: vec![Modifier {
+                uuid: Uuid::from_bytes(&[0x66; 16]).unwrap(),
+                amount: 0.3,
+                operation: ModifierOperation::Multiply
+            }]
+        };
+        let mut dst = Vec::new();
+        <Property as Protocol>::proto_encode(&value, &mut dst).unwrap();
+        assert_eq!(<Property as Protocol>::proto_len(&value), dst.len());
+        assert_eq!(dst.len(), 1 + 21 + 8 + 1 + 16 + 8 + 1);
+        assert_eq!(dst[dst.len() - 1], 2);
+        let decoded = <Property as Protocol>::proto_decode(&mut io::Cursor::new(dst)).unwrap();
+        assert_eq!(decoded.key, "generic.movementSpeed");
+        assert_eq!(decoded.modifiers.len(), 1);
+        assert_eq!(decoded.modifiers[0].operation, ModifierOperation::Multiply);
+    }
+
+    #[test]
+    fn entity_properties_roundtrip() {
+        let packet = EntityProperties {
+            entity_id: 7,
+            properties: vec![Property {
+                key: MOVEMENT_SPEED.to_string(),
+                value: 0.1,
+                modifiers: vec![Modifier {
+                    uuid: Uuid::from_bytes(&[0x66; 16]).unwrap(),
+                    amount: 0.3,
+                    operation: ModifierOperation::Multiply
+                }]
+            }]
+        };
+        let mut dst = Vec::new();
+        packet.write(&mut dst, None).unwrap();
+        assert_eq!(&dst[..7], &[6 + 56, 0x20, 7, 0, 0, 0, 1]);
+        let mut src = io::Cursor::new(dst);
+        match read_packet(Direction::Clientbound, State::Play, None, &mut src).unwrap() {
+            PacketEnum::Play(play::PacketEnum::Clientbound(play::clientbound::PacketEnum::EntityProperties(decoded))) => {
+                assert_eq!(decoded.entity_id, 7);
+                assert_eq!(decoded.properties.len(), 1);
+                assert_eq!(decoded.properties[0].key, MOVEMENT_SPEED);
+                assert_eq!(decoded.properties[0].value, 0.1);
+                assert_eq!(decoded.properties[0].modifiers[0].amount, 0.3);
+                assert_eq!(decoded.properties[0].modifiers[0].operation, ModifierOperation::Multiply);
+            }
+            _ => panic!("decoded the wrong packet")
+        }
+    }
+
+    #[test]
+    fn entity_use_interact_at() {
+        let value = EntityUseAction::InteractAt([0.5, 1.0, -0.5]);
+        let bytes = vec![2, 0x3f, 0, 0, 0, 0x3f, 0x80, 0, 0, 0xbf, 0, 0, 0];
+        let mut dst = Vec::new();
+        <EntityUseAction as Protocol>::proto_encode(&value, &mut dst).unwrap();
+        assert_eq!(&dst, &bytes);
+        assert_eq!(<EntityUseAction as Protocol>::proto_len(&value), bytes.len());
+        assert_eq!(<EntityUseAction as Protocol>::proto_decode(&mut io::Cursor::new(bytes)).unwrap(), value);
+    }
+
+    #[test]
+    fn entity_use_attack() {
+        let mut src = io::Cursor::new(vec![1]);
+        assert_eq!(<EntityUseAction as Protocol>::proto_decode(&mut src).unwrap(), EntityUseAction::Attack);
+    }
+
+    #[test]
+    fn packet_registry() {
+        let info = packet_info(State::Handshaking, Direction::Serverbound, 0x00).unwrap();
+        assert_eq!(info.name, "Handshake");
+        assert_eq!(info.fields.len(), 4);
+        assert_eq!(info.fields[0].0, "proto_version");
+        assert_eq!(info.fields[3], ("next_state", "State"));
+
+        let info = packet_info(State::Status, Direction::Serverbound, 0x00).unwrap();
+        assert_eq!(info.name, "StatusRequest");
+        assert!(info.fields.is_empty());
+
+        let info = packet_info(State::Play, Direction::Clientbound, 0x26).unwrap();
+        assert_eq!(info.name, "MapChunkBulk");
+        assert_eq!(info.fields[0].0, "sky_light_sent");
+
+        assert!(packet_info(State::Handshaking, Direction::Clientbound, 0x00).is_none());
+        for info in PACKETS.iter() {
+            assert_eq!(PACKETS.iter().filter(|other| other.state == info.state && other.direction == info.direction && other.id == info.id).count(), 1);
+        }
+    }
+
+    #[test]
+    fn packet_enum_write() {
+        let mut frame = Vec::new();
+        StatusResponse { response: "{}".to_string() }.write(&mut frame, None).unwrap();
+        let packet = decode_packet(Direction::Clientbound, State::Status, &frame[1..]).unwrap();
+        assert_eq!(packet.id(), 0x00);
+        assert_eq!(packet.len(), 3);
+
+        let mut dst = Vec::new();
+        packet.write(&mut dst, None).unwrap();
+        assert_eq!(&dst, &frame);
+
+        let mut body = Vec::new();
+        match packet {
+            PacketEnum::Status(status::PacketEnum::Clientbound(ref packet)) => packet.encode(&mut body).unwrap(),
+            _ => panic!("decoded the wrong packet")
+        }
+        assert_eq!(&body, &frame[2..]);
+    }
+
+    #[test]
+    fn error_field_path() {
+        // A ClickWindow whose clicked item has a compound tag cut off after its type.
+        let frame = [0x0e, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0x0a];
+        let err = decode_packet(Direction::Serverbound, State::Play, &frame).err().unwrap();
+        assert_eq!(err.packet, Some("ClickWindow"));
+        assert_eq!(err.location(), "ClickWindow.clicked_item.tag");
+
+        let frame = [0x02, 1, 0x7b, 0];
+        let err = decode_packet(Direction::Clientbound, State::Play, &frame).err().unwrap();
+        assert_eq!(err.location(), "ChatMessage.data");
+
+        // Packets with a hand-written decoder fill in their fields as well.
+        let frame = [0x2d, 0, 3, 0x46, 0x6f, 0x6f];
+        let err = decode_packet(Direction::Clientbound, State::Play, &frame).err().unwrap();
+        assert_eq!(err.location(), "OpenWindow.window_title");
+        let frame = [0x17, 5, 0x4d, 0x43];
+        let err = decode_packet(Direction::Serverbound, State::Play, &frame).err().unwrap();
+        assert_eq!(err.location(), "PluginMessage.channel");
+    }
+
+    #[test]
+    fn error_unknown_packet_id() {
+        match decode_packet(Direction::Clientbound, State::Status, &[0x05]).err().unwrap().kind {
+            ErrorKind::UnknownPacketId { state: State::Status, direction: Direction::Clientbound, id: 0x05 } => {}
+            kind => panic!("unexpected error {:?}", kind)
+        }
+    }
+
+    #[test]
+    fn field_limits() {
+        let mut config = LimitConfig::new(Default::default());
+        config.set("ChatMessage.message", Limits { max_string_len: 2, ..Default::default() });
+        limits::configure(config);
+        let long = decode_packet(Direction::Serverbound, State::Play, &[0x01, 3, 0x68, 0x69, 0x21]);
+        let short = decode_packet(Direction::Serverbound, State::Play, &[0x01, 2, 0x68, 0x69]);
+        let other = decode_packet(Direction::Serverbound, State::Login, &[0x00, 3, 0x68, 0x69, 0x21]);
+        limits::configure(Default::default());
+
+        let err = long.err().unwrap();
+        assert_eq!(err.location(), "ChatMessage.message");
+        match err.kind {
+            ErrorKind::StringTooLong { len: 3, max: 2 } => {}
+            kind => panic!("unexpected error {:?}", kind)
+        }
+        assert!(short.is_ok());
+        assert!(other.is_ok());
+    }
+
+    #[test]
+    fn hand_written_field_limits() {
+        let mut config = LimitConfig::new(Default::default());
+        config.set("PluginMessage.channel", Limits { max_string_len: 2, ..Default::default() });
+        limits::configure(config);
+        let result = decode_packet(Direction::Serverbound, State::Play, &[0x17, 3, 0x4d, 0x43, 0x7c, 1, 2]);
+        limits::configure(Default::default());
+
+        let err = result.err().unwrap();
+        assert_eq!(err.location(), "PluginMessage.channel");
+        match err.kind {
+            ErrorKind::StringTooLong { len: 3, max: 2 } => {}
+            kind => panic!("unexpected error {:?}", kind)
+        }
+    }
+
+    #[test]
+    fn vanilla_field_limits() {
+        // A 17 character name in LoginStart.
+        let mut frame = vec![0x00, 17];
+        frame.extend(iter::repeat(0x61).take(17));
+        match decode_packet(Direction::Serverbound, State::Login, &frame).err().unwrap().kind {
+            ErrorKind::StringTooLong { len: 17, max: 16 } => {}
+            kind => panic!("unexpected error {:?}", kind)
+        }
+        // A length too large for 16 characters is rejected before reading the name.
+        let frame = vec![0x00, 65];
+        match decode_packet(Direction::Serverbound, State::Login, &frame).err().unwrap().kind {
+            ErrorKind::LimitExceeded { limit: "string length in bytes", max: 64 } => {}
+            kind => panic!("unexpected error {:?}", kind)
+        }
+    }
+
+    #[test]
+    fn frame_size_limit() {
+        // A frame length of 2097152, one byte over the vanilla limit.
+        let mut src = io::Cursor::new(vec![0x80, 0x80, 0x80, 0x01]);
+        match read_packet(Direction::Clientbound, State::Status, None, &mut src).err().unwrap().kind {
+            ErrorKind::LimitExceeded { limit: "frame size", max: 2097151 } => {}
+            kind => panic!("unexpected error {:?}", kind)
+        }
+    }
+}
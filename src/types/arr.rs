@@ -1,11 +1,12 @@
 //! Minecraft protocol length-prefixed array data type
 
-use std::io;
 use std::io::prelude::*;
 use std::iter::{ AdditiveIterator, FromIterator };
 use std::marker::PhantomData;
 use std::num::{ NumCast, ToPrimitive };
 
+use error::{Error, Result};
+use limits;
 use packet::Protocol;
 
 pub struct Arr<L, T>(PhantomData<(fn() -> L, T)>);
@@ -19,8 +20,8 @@ impl<L: Protocol, T: Protocol> Protocol for Arr<L, T> where L::Clean: NumCast {
         len_len + len_values
     }
 
-    fn proto_encode(value: &Vec<T::Clean>, dst: &mut Write) -> io::Result<()> {
-        let len = try!(<L::Clean as NumCast>::from(value.len()).ok_or(io::Error::new(io::ErrorKind::InvalidInput, "could not convert length of vector to Array length type", None)));
+    fn proto_encode(value: &Vec<T::Clean>, dst: &mut Write) -> Result<()> {
+        let len = try!(<L::Clean as NumCast>::from(value.len()).ok_or(Error::invalid("could not convert length of vector to Array length type", None)));
         try!(<L as Protocol>::proto_encode(&len, dst));
         for elt in value {
             try!(<T as Protocol>::proto_encode(elt, dst));
@@ -28,9 +29,10 @@ impl<L: Protocol, T: Protocol> Protocol for Arr<L, T> where L::Clean: NumCast {
         Ok(())
     }
 
-    fn proto_decode(src: &mut Read) -> io::Result<Vec<T::Clean>> {
-        let len = try!(try!(<L as Protocol>::proto_decode(src)).to_uint().ok_or(io::Error::new(io::ErrorKind::InvalidInput, "could not read length of vector from Array length type", None)));
-        <io::Result<Vec<T::Clean>> as FromIterator<_>>::from_iter((0..len).map(|_| <T as Protocol>::proto_decode(src)))
+    fn proto_decode(src: &mut Read) -> Result<Vec<T::Clean>> {
+        let len = try!(try!(<L as Protocol>::proto_decode(src)).to_uint().ok_or(Error::invalid("could not read length of vector from Array length type", None)));
+        try!(limits::check("array length", len, limits::current().max_array_len));
+        <Result<Vec<T::Clean>> as FromIterator<_>>::from_iter((0..len).map(|_| <T as Protocol>::proto_decode(src)))
     }
 }
 
@@ -40,6 +42,10 @@ mod tests {
 
     use std::io;
 
+    use std::default::Default;
+
+    use error::ErrorKind;
+    use limits::{self, Limits};
     use packet::Protocol;
     use types::Var;
 
@@ -86,4 +92,22 @@ mod tests {
         let value = <Arr<i32, i32> as Protocol>::proto_decode(&mut src).unwrap();
         assert_eq!(arr, value);
     }
+
+    #[test]
+    fn arr_decode_limits() {
+        let mut src = io::Cursor::new(vec![0xff, 0xff, 0xff, 0xff]);
+        assert!(<Arr<i32, u8> as Protocol>::proto_decode(&mut src).is_err());
+
+        let _limits = limits::scope(Limits { max_array_len: 2, ..Default::default() });
+        let mut src = io::Cursor::new(vec![0x7f, 0xff, 0xff, 0xff]);
+        match <Arr<i32, u8> as Protocol>::proto_decode(&mut src) {
+            Err(err) => match err.kind {
+                ErrorKind::LimitExceeded { max: 2, .. } => {}
+                kind => panic!("unexpected error kind {:?}", kind)
+            },
+            Ok(_) => panic!("decoded an array over the limit")
+        }
+        let mut src = io::Cursor::new(vec![2, 0, 1]);
+        assert_eq!(<Arr<u8, u8> as Protocol>::proto_decode(&mut src).unwrap(), vec![0, 1]);
+    }
 }
@@ -0,0 +1,484 @@
+//! MC Protocol Chat (JSON text component) data type.
+
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+
+use rustc_serialize::json::{Json, ToJson};
+
+use error::{Error, Result};
+use limits;
+use packet::Protocol;
+
+/// The color of a chat component.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+    Reset
+}
+
+impl Color {
+    /// The name used for this color in JSON.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Color::Black       => "black",
+            Color::DarkBlue    => "dark_blue",
+            Color::DarkGreen   => "dark_green",
+            Color::DarkAqua    => "dark_aqua",
+            Color::DarkRed     => "dark_red",
+            Color::DarkPurple  => "dark_purple",
+            Color::Gold        => "gold",
+            Color::Gray        => "gray",
+            Color::DarkGray    => "dark_gray",
+            Color::Blue        => "blue",
+            Color::Green       => "green",
+            Color::Aqua        => "aqua",
+            Color::Red         => "red",
+            Color::LightPurple => "light_purple",
+            Color::Yellow      => "yellow",
+            Color::White       => "white",
+            Color::Reset       => "reset"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Color> {
+        match name {
+            "black"        => Some(Color::Black),
+            "dark_blue"    => Some(Color::DarkBlue),
+            "dark_green"   => Some(Color::DarkGreen),
+            "dark_aqua"    => Some(Color::DarkAqua),
+            "dark_red"     => Some(Color::DarkRed),
+            "dark_purple"  => Some(Color::DarkPurple),
+            "gold"         => Some(Color::Gold),
+            "gray"         => Some(Color::Gray),
+            "dark_gray"    => Some(Color::DarkGray),
+            "blue"         => Some(Color::Blue),
+            "green"        => Some(Color::Green),
+            "aqua"         => Some(Color::Aqua),
+            "red"          => Some(Color::Red),
+            "light_purple" => Some(Color::LightPurple),
+            "yellow"       => Some(Color::Yellow),
+            "white"        => Some(Color::White),
+            "reset"        => Some(Color::Reset),
+            _ => None
+        }
+    }
+}
+
+/// What happens when a chat component is clicked.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClickEvent {
+    OpenUrl(String),
+    RunCommand(String),
+    SuggestCommand(String),
+    ChangePage(String)
+}
+
+/// What is shown when the mouse hovers over a chat component.
+///
+/// Items and entities are given as stringified NBT, as in vanilla.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HoverEvent {
+    ShowText(Box<Chat>),
+    ShowAchievement(String),
+    ShowItem(String),
+    ShowEntity(String)
+}
+
+/// The content of a chat component, not including its children.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChatContent {
+    Text(String),
+    /// A translation key, with the components to substitute into it.
+    Translate(String, Vec<Chat>),
+    Score { name: String, objective: String },
+    Selector(String)
+}
+
+/// A chat component, encoded as a JSON string.
+///
+/// Formatting options which are `None` are inherited from the parent component.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chat {
+    pub content: ChatContent,
+    pub color: Option<Color>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underlined: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub obfuscated: Option<bool>,
+    pub insertion: Option<String>,
+    pub click_event: Option<ClickEvent>,
+    pub hover_event: Option<HoverEvent>,
+    pub extra: Vec<Chat>
+}
+
+fn invalid_chat(detail: String) -> Error {
+    Error::invalid("invalid chat component", Some(detail))
+}
+
+/// Fails if the arrays and objects of `json` are nested deeper than `max`, without parsing it,
+/// since both the parser and `Chat::from_json` recurse once per level.
+fn check_depth(json: &str, max: usize) -> Result<()> {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for &b in json.as_bytes().iter() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if b == b'\\' {
+                escaped = true;
+            } else if b == b'"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                try!(limits::check("chat depth", depth, max));
+            }
+            // Unbalanced brackets are left to the parser.
+            b']' | b'}' => if depth > 0 { depth -= 1 },
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn get_string(obj: &BTreeMap<String, Json>, key: &str) -> Result<Option<String>> {
+    match obj.get(key) {
+        Some(&Json::String(ref s)) => Ok(Some(s.clone())),
+        Some(json) => Err(invalid_chat(format!("expected a string for {}, found {}", key, json))),
+        None => Ok(None)
+    }
+}
+
+fn get_bool(obj: &BTreeMap<String, Json>, key: &str) -> Result<Option<bool>> {
+    match obj.get(key) {
+        Some(&Json::Boolean(b)) => Ok(Some(b)),
+        Some(json) => Err(invalid_chat(format!("expected a bool for {}, found {}", key, json))),
+        None => Ok(None)
+    }
+}
+
+impl Chat {
+    /// A plain text component without any formatting.
+    pub fn text(text: &str) -> Chat {
+        Chat::new(ChatContent::Text(text.to_string()))
+    }
+
+    /// A translated component without any formatting.
+    pub fn translate(key: &str, with: Vec<Chat>) -> Chat {
+        Chat::new(ChatContent::Translate(key.to_string(), with))
+    }
+
+    pub fn new(content: ChatContent) -> Chat {
+        Chat {
+            content: content,
+            color: None,
+            bold: None,
+            italic: None,
+            underlined: None,
+            strikethrough: None,
+            obfuscated: None,
+            insertion: None,
+            click_event: None,
+            hover_event: None,
+            extra: Vec::new()
+        }
+    }
+
+    /// Reads a chat component from its JSON representation.
+    ///
+    /// Plain strings and arrays are accepted as well, like the vanilla client does.
+    pub fn from_json(json: &Json) -> Result<Chat> {
+        let obj = match *json {
+            Json::String(ref s) => return Ok(Chat::text(s)),
+            Json::Array(ref parts) if !parts.is_empty() => {
+                let mut chat = try!(Chat::from_json(&parts[0]));
+                for part in parts[1..].iter() {
+                    chat.extra.push(try!(Chat::from_json(part)));
+                }
+                return Ok(chat);
+            }
+            Json::Object(ref obj) => obj,
+            ref json => return Err(invalid_chat(format!("expected an object, found {}", json)))
+        };
+        let content = if let Some(text) = try!(get_string(obj, "text")) {
+            ChatContent::Text(text)
+        } else if let Some(key) = try!(get_string(obj, "translate")) {
+            let mut with = Vec::new();
+            match obj.get("with") {
+                Some(&Json::Array(ref args)) => for arg in args.iter() {
+                    with.push(try!(Chat::from_json(arg)));
+                },
+                Some(json) => return Err(invalid_chat(format!("expected an array for with, found {}", json))),
+                None => {}
+            }
+            ChatContent::Translate(key, with)
+        } else if let Some(&Json::Object(ref score)) = obj.get("score") {
+            ChatContent::Score {
+                name: try!(get_string(score, "name")).unwrap_or(String::new()),
+                objective: try!(get_string(score, "objective")).unwrap_or(String::new())
+            }
+        } else if let Some(selector) = try!(get_string(obj, "selector")) {
+            ChatContent::Selector(selector)
+        } else {
+            ChatContent::Text(String::new())
+        };
+        let mut chat = Chat::new(content);
+        if let Some(color) = try!(get_string(obj, "color")) {
+            chat.color = Some(try!(Color::from_name(&color).ok_or(invalid_chat(format!("unknown color {}", color)))));
+        }
+        chat.bold = try!(get_bool(obj, "bold"));
+        chat.italic = try!(get_bool(obj, "italic"));
+        chat.underlined = try!(get_bool(obj, "underlined"));
+        chat.strikethrough = try!(get_bool(obj, "strikethrough"));
+        chat.obfuscated = try!(get_bool(obj, "obfuscated"));
+        chat.insertion = try!(get_string(obj, "insertion"));
+        if let Some(&Json::Object(ref event)) = obj.get("clickEvent") {
+            let value = try!(get_string(event, "value")).unwrap_or(String::new());
+            chat.click_event = Some(match try!(get_string(event, "action")) {
+                Some(ref action) if *action == "open_url" => ClickEvent::OpenUrl(value),
+                Some(ref action) if *action == "run_command" => ClickEvent::RunCommand(value),
+                Some(ref action) if *action == "suggest_command" => ClickEvent::SuggestCommand(value),
+                Some(ref action) if *action == "change_page" => ClickEvent::ChangePage(value),
+                action => return Err(invalid_chat(format!("unknown click event action {:?}", action)))
+            });
+        }
+        if let Some(&Json::Object(ref event)) = obj.get("hoverEvent") {
+            let action = try!(get_string(event, "action"));
+            if action.as_ref().map(|action| &action[..]) == Some("show_text") {
+                let value = match event.get("value") {
+                    Some(value) => try!(Chat::from_json(value)),
+                    None => Chat::text("")
+                };
+                chat.hover_event = Some(HoverEvent::ShowText(Box::new(value)));
+            } else {
+                let value = match event.get("value") {
+                    Some(&Json::String(ref s)) => s.clone(),
+                    Some(value) => try!(Chat::from_json(value)).to_plain(),
+                    None => String::new()
+                };
+                chat.hover_event = Some(match action {
+                    Some(ref action) if *action == "show_achievement" => HoverEvent::ShowAchievement(value),
+                    Some(ref action) if *action == "show_item" => HoverEvent::ShowItem(value),
+                    Some(ref action) if *action == "show_entity" => HoverEvent::ShowEntity(value),
+                    action => return Err(invalid_chat(format!("unknown hover event action {:?}", action)))
+                });
+            }
+        }
+        match obj.get("extra") {
+            Some(&Json::Array(ref extra)) => for part in extra.iter() {
+                chat.extra.push(try!(Chat::from_json(part)));
+            },
+            Some(json) => return Err(invalid_chat(format!("expected an array for extra, found {}", json))),
+            None => {}
+        }
+        Ok(chat)
+    }
+
+    /// The text of this component and its children, without formatting.
+    ///
+    /// Translation keys, scores and selectors are left as they are.
+    pub fn to_plain(&self) -> String {
+        let mut s = match self.content {
+            ChatContent::Text(ref text) => text.clone(),
+            ChatContent::Translate(ref key, _) => key.clone(),
+            ChatContent::Score { ref name, .. } => name.clone(),
+            ChatContent::Selector(ref selector) => selector.clone()
+        };
+        for part in self.extra.iter() {
+            s.push_str(&part.to_plain());
+        }
+        s
+    }
+}
+
+impl ToJson for Chat {
+    fn to_json(&self) -> Json {
+        let mut obj = BTreeMap::new();
+        match self.content {
+            ChatContent::Text(ref text) => {
+                obj.insert("text".to_string(), text.to_json());
+            }
+            ChatContent::Translate(ref key, ref with) => {
+                obj.insert("translate".to_string(), key.to_json());
+                if !with.is_empty() {
+                    obj.insert("with".to_string(), Json::Array(with.iter().map(|arg| arg.to_json()).collect()));
+                }
+            }
+            ChatContent::Score { ref name, ref objective } => {
+                let mut score = BTreeMap::new();
+                score.insert("name".to_string(), name.to_json());
+                score.insert("objective".to_string(), objective.to_json());
+                obj.insert("score".to_string(), Json::Object(score));
+            }
+            ChatContent::Selector(ref selector) => {
+                obj.insert("selector".to_string(), selector.to_json());
+            }
+        }
+        if let Some(color) = self.color {
+            obj.insert("color".to_string(), color.name().to_json());
+        }
+        let flags = [
+            ("bold", self.bold),
+            ("italic", self.italic),
+            ("underlined", self.underlined),
+            ("strikethrough", self.strikethrough),
+            ("obfuscated", self.obfuscated)
+        ];
+        for &(name, flag) in flags.iter() {
+            if let Some(flag) = flag {
+                obj.insert(name.to_string(), Json::Boolean(flag));
+            }
+        }
+        if let Some(ref insertion) = self.insertion {
+            obj.insert("insertion".to_string(), insertion.to_json());
+        }
+        if let Some(ref event) = self.click_event {
+            let (action, value) = match *event {
+                ClickEvent::OpenUrl(ref value) => ("open_url", value),
+                ClickEvent::RunCommand(ref value) => ("run_command", value),
+                ClickEvent::SuggestCommand(ref value) => ("suggest_command", value),
+                ClickEvent::ChangePage(ref value) => ("change_page", value)
+            };
+            let mut event = BTreeMap::new();
+            event.insert("action".to_string(), action.to_json());
+            event.insert("value".to_string(), value.to_json());
+            obj.insert("clickEvent".to_string(), Json::Object(event));
+        }
+        if let Some(ref event) = self.hover_event {
+            let (action, value) = match *event {
+                HoverEvent::ShowText(ref value) => ("show_text", value.to_json()),
+                HoverEvent::ShowAchievement(ref value) => ("show_achievement", value.to_json()),
+                HoverEvent::ShowItem(ref value) => ("show_item", value.to_json()),
+                HoverEvent::ShowEntity(ref value) => ("show_entity", value.to_json())
+            };
+            let mut event = BTreeMap::new();
+            event.insert("action".to_string(), action.to_json());
+            event.insert("value".to_string(), value);
+            obj.insert("hoverEvent".to_string(), Json::Object(event));
+        }
+        if !self.extra.is_empty() {
+            obj.insert("extra".to_string(), Json::Array(self.extra.iter().map(|part| part.to_json()).collect()));
+        }
+        Json::Object(obj)
+    }
+}
+
+/// Chat components are sent as JSON in a `String`.
+impl Protocol for Chat {
+    type Clean = Chat;
+
+    fn proto_len(value: &Chat) -> usize {
+        <String as Protocol>::proto_len(&value.to_json().to_string())
+    }
+
+    fn proto_encode(value: &Chat, dst: &mut Write) -> Result<()> {
+        <String as Protocol>::proto_encode(&value.to_json().to_string(), dst)
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<Chat> {
+        let s = try!(<String as Protocol>::proto_decode(src));
+        try!(check_depth(&s, limits::current().max_chat_depth));
+        let json = try!(Json::from_str(&s).map_err(|err| invalid_chat(format!("JSON error: {:?}", err))));
+        Chat::from_json(&json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use packet::Protocol;
+
+    #[test]
+    fn chat_encode_text() {
+        let mut dst = Vec::new();
+        <Chat as Protocol>::proto_encode(&Chat::text("hi"), &mut dst).unwrap();
+        let mut bytes = vec![13];
+        bytes.extend(br#"{"text":"hi"}"#.iter().map(|&b| b));
+        assert_eq!(&dst, &bytes);
+        assert_eq!(<Chat as Protocol>::proto_len(&Chat::text("hi")), bytes.len());
+    }
+
+    #[test]
+    fn chat_decode_string_and_array() {
+        let json = br#"["a","b"]"#;
+        let mut bytes = vec![json.len() as u8];
+        bytes.extend(json.iter().map(|&b| b));
+        let chat = <Chat as Protocol>::proto_decode(&mut io::Cursor::new(bytes)).unwrap();
+        let mut expected = Chat::text("a");
+        expected.extra.push(Chat::text("b"));
+        assert_eq!(chat, expected);
+    }
+
+    #[test]
+    fn chat_roundtrip() {
+        let mut chat = Chat::translate("chat.type.text", vec![Chat::text("Herobrine"), Chat::text("hello")]);
+        chat.color = Some(Color::Yellow);
+        chat.bold = Some(true);
+        chat.click_event = Some(ClickEvent::SuggestCommand("/msg Herobrine ".to_string()));
+        chat.hover_event = Some(HoverEvent::ShowText(Box::new(Chat::text("Click to reply"))));
+        let mut score = Chat::new(ChatContent::Score { name: "Herobrine".to_string(), objective: "kills".to_string() });
+        score.italic = Some(false);
+        chat.extra.push(score);
+        chat.extra.push(Chat::new(ChatContent::Selector("@p".to_string())));
+
+        let mut dst = Vec::new();
+        <Chat as Protocol>::proto_encode(&chat, &mut dst).unwrap();
+        assert_eq!(<Chat as Protocol>::proto_len(&chat), dst.len());
+        let decoded = <Chat as Protocol>::proto_decode(&mut io::Cursor::new(dst)).unwrap();
+        assert_eq!(decoded, chat);
+    }
+
+    #[test]
+    fn chat_decode_invalid_color() {
+        let json = br#"{"text":"a","color":"pink"}"#;
+        let mut bytes = vec![json.len() as u8];
+        bytes.extend(json.iter().map(|&b| b));
+        assert!(<Chat as Protocol>::proto_decode(&mut io::Cursor::new(bytes)).is_err());
+    }
+
+    #[test]
+    fn chat_decode_too_deep() {
+        use error::ErrorKind;
+        use limits::{self, Limits};
+        use std::default::Default;
+
+        let _limits = limits::scope(Limits { max_chat_depth: 2, ..Default::default() });
+        // Brackets in strings don't count.
+        let json = br#"[["[[["]]"#;
+        let mut bytes = vec![json.len() as u8];
+        bytes.extend(json.iter().map(|&b| b));
+        assert!(<Chat as Protocol>::proto_decode(&mut io::Cursor::new(bytes)).is_ok());
+
+        let json = br#"[[{"text":"a"}]]"#;
+        let mut bytes = vec![json.len() as u8];
+        bytes.extend(json.iter().map(|&b| b));
+        match <Chat as Protocol>::proto_decode(&mut io::Cursor::new(bytes)).err().unwrap().kind {
+            ErrorKind::LimitExceeded { limit: "chat depth", max: 2 } => {}
+            kind => panic!("unexpected error {:?}", kind)
+        }
+    }
+}
@@ -1,8 +1,9 @@
 //! MC Protocol Chunk data types.
 
-use std::io::{self, Cursor};
+use std::io::Cursor;
 use std::io::prelude::*;
 
+use error::Result;
 use packet::play::clientbound::ChunkData;
 use packet::Protocol;
 use types::consts::Dimension;
@@ -25,7 +26,7 @@ impl ChunkColumn {
         };
         chunks + biomes
     }
-    pub fn encode(&self) -> io::Result<Vec<u8>> {
+    pub fn encode(&self) -> Result<Vec<u8>> {
         let mut dst: Cursor<Vec<u8>> = Cursor::new(Vec::new());
         for chunk in self.chunks.iter() {
             for x in chunk.blocks.iter() {
@@ -47,7 +48,7 @@ impl ChunkColumn {
         }
         Ok(dst.into_inner())
     }
-    pub fn decode(packet: ChunkData, dimension: Dimension) -> io::Result<ChunkColumn> {
+    pub fn decode(packet: ChunkData, dimension: Dimension) -> Result<ChunkColumn> {
         use std::num::Int;
 
         let mut src = Cursor::new(packet.chunk_data);
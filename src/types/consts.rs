@@ -1,9 +1,9 @@
 //! MC Protocol constants.
 
 use std::io::prelude::*;
-use std::io;
 use std::num::FromPrimitive;
 
+use error::{Error, ErrorKind, Result};
 use packet::Protocol;
 
 macro_rules! enum_protocol_impl {
@@ -14,17 +14,17 @@ macro_rules! enum_protocol_impl {
             #[allow(unused_variables)]
             fn proto_len(value: &$name) -> usize { <$repr as Protocol>::proto_len(&(*value as $repr)) }
 
-            fn proto_encode(value: &$name, mut dst: &mut Write) -> io::Result<()> {
+            fn proto_encode(value: &$name, mut dst: &mut Write) -> Result<()> {
                 let repr = *value as $repr;
                 try!(<$repr as Protocol>::proto_encode(&repr, dst));
                 Ok(())
             }
 
-            fn proto_decode(mut src: &mut Read) -> io::Result<$name> {
+            fn proto_decode(mut src: &mut Read) -> Result<$name> {
                 let value = try!(<$repr as Protocol>::proto_decode(src));
                 match FromPrimitive::$dec_repr(value) {
                     Some(x) => Ok(x),
-                    None => Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid enum", None))
+                    None => Err(Error::new(ErrorKind::InvalidEnumValue { name: stringify!($name), value: value as i64 }))
                 }
             }
         }
@@ -32,6 +32,8 @@ macro_rules! enum_protocol_impl {
 }
 
 enum_protocol_impl!(Dimension, i8, from_i8);
+enum_protocol_impl!(BlockEntityAction, u8, from_u8);
+enum_protocol_impl!(ModifierOperation, i8, from_i8);
 
 #[repr(i8)]
 #[derive(Copy, Debug, FromPrimitive, PartialEq)]
@@ -40,3 +42,35 @@ pub enum Dimension {
     Overworld = 0,
     End = 1
 }
+
+/// The kind of block entity updated by the `UpdateBlockEntity` packet.
+///
+/// Signs are not included, since their text is sent with `UpdateSign` instead.
+#[repr(u8)]
+#[derive(Copy, Debug, FromPrimitive, PartialEq)]
+pub enum BlockEntityAction {
+    MobSpawner = 1,
+    CommandBlock = 2,
+    Beacon = 3,
+    Skull = 4,
+    FlowerPot = 5,
+    Banner = 6
+}
+
+/// How an attribute modifier from the `EntityProperties` packet is applied.
+#[repr(i8)]
+#[derive(Copy, Debug, FromPrimitive, PartialEq)]
+pub enum ModifierOperation {
+    Add = 0,
+    AddPercent = 1,
+    Multiply = 2
+}
+
+/// Attribute keys understood by the vanilla client in `EntityProperties`.
+pub const MAX_HEALTH: &'static str = "generic.maxHealth";
+pub const FOLLOW_RANGE: &'static str = "generic.followRange";
+pub const KNOCKBACK_RESISTANCE: &'static str = "generic.knockbackResistance";
+pub const MOVEMENT_SPEED: &'static str = "generic.movementSpeed";
+pub const ATTACK_DAMAGE: &'static str = "generic.attackDamage";
+pub const HORSE_JUMP_STRENGTH: &'static str = "horse.jumpStrength";
+pub const ZOMBIE_SPAWN_REINFORCEMENTS: &'static str = "zombie.spawnReinforcements";
@@ -0,0 +1,124 @@
+//! Fixed-point positions and packed angles used by entity packets.
+
+use std::io::prelude::*;
+use std::marker::PhantomData;
+use std::num::Float;
+use std::{i8, i32};
+
+use error::{Error, Result};
+use packet::Protocol;
+
+/// Fixed-point number with 5 fractional bits, i.e. in units of 1/32 block.
+///
+/// `Fixed<i32>` is used for absolute positions and `Fixed<i8>` for relative moves.
+pub struct Fixed<T>(PhantomData<fn() -> T>);
+
+/// Converts `value` to 1/32 units, failing if it doesn't fit between `min` and `max`.
+fn to_fixed(value: f64, min: f64, max: f64) -> Result<f64> {
+    let scaled = (value * 32.0).round();
+    if scaled >= min && scaled <= max {
+        Ok(scaled)
+    } else {
+        Err(Error::invalid("fixed-point value out of range", Some(format!("{} can't be represented in units of 1/32 between {} and {}", value, min / 32.0, max / 32.0))))
+    }
+}
+
+impl Protocol for Fixed<i32> {
+    type Clean = f64;
+
+    #[allow(unused_variables)]
+    fn proto_len(value: &f64) -> usize { 4 }
+
+    fn proto_encode(value: &f64, dst: &mut Write) -> Result<()> {
+        let fixed = try!(to_fixed(*value, i32::MIN as f64, i32::MAX as f64));
+        <i32 as Protocol>::proto_encode(&(fixed as i32), dst)
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<f64> {
+        let fixed = try!(<i32 as Protocol>::proto_decode(src));
+        Ok(fixed as f64 / 32.0)
+    }
+}
+
+impl Protocol for Fixed<i8> {
+    type Clean = f64;
+
+    #[allow(unused_variables)]
+    fn proto_len(value: &f64) -> usize { 1 }
+
+    /// Writes `value` into `dst`, failing for moves of 4 blocks or more, which need a teleport.
+    fn proto_encode(value: &f64, dst: &mut Write) -> Result<()> {
+        let fixed = try!(to_fixed(*value, i8::MIN as f64, i8::MAX as f64));
+        <i8 as Protocol>::proto_encode(&(fixed as i8), dst)
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<f64> {
+        let fixed = try!(<i8 as Protocol>::proto_decode(src));
+        Ok(fixed as f64 / 32.0)
+    }
+}
+
+/// Angle in steps of 1/256 of a full turn, with a Clean value in degrees.
+///
+/// Angles outside of 0 to 360 degrees are wrapped around when encoding.
+pub struct Angle;
+
+impl Protocol for Angle {
+    type Clean = f32;
+
+    #[allow(unused_variables)]
+    fn proto_len(value: &f32) -> usize { 1 }
+
+    fn proto_encode(value: &f32, dst: &mut Write) -> Result<()> {
+        if !value.is_finite() {
+            return Err(Error::invalid("angle out of range", Some(format!("{} is not a finite angle", value))));
+        }
+        let steps = (*value % 360.0 / 360.0 * 256.0).round() as i32;
+        <u8 as Protocol>::proto_encode(&(((steps % 256 + 256) % 256) as u8), dst)
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<f32> {
+        let steps = try!(<u8 as Protocol>::proto_decode(src));
+        Ok(steps as f32 * 360.0 / 256.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use packet::Protocol;
+
+    #[test]
+    fn fixed_i32_roundtrip() {
+        let mut dst = Vec::new();
+        <Fixed<i32> as Protocol>::proto_encode(&-1.5, &mut dst).unwrap();
+        assert_eq!(&dst, &vec![0xff, 0xff, 0xff, 0xd0]);
+        let value = <Fixed<i32> as Protocol>::proto_decode(&mut io::Cursor::new(dst)).unwrap();
+        assert_eq!(value, -1.5);
+    }
+
+    #[test]
+    fn fixed_i8_limits() {
+        let mut dst = Vec::new();
+        <Fixed<i8> as Protocol>::proto_encode(&3.96875, &mut dst).unwrap();
+        <Fixed<i8> as Protocol>::proto_encode(&-4.0, &mut dst).unwrap();
+        assert_eq!(&dst, &vec![0x7f, 0x80]);
+        assert!(<Fixed<i8> as Protocol>::proto_encode(&4.0, &mut dst).is_err());
+        assert!(<Fixed<i8> as Protocol>::proto_encode(&-4.1, &mut dst).is_err());
+        assert_eq!(dst.len(), 2);
+    }
+
+    #[test]
+    fn angle_wraps() {
+        let mut dst = Vec::new();
+        <Angle as Protocol>::proto_encode(&90.0, &mut dst).unwrap();
+        <Angle as Protocol>::proto_encode(&-90.0, &mut dst).unwrap();
+        <Angle as Protocol>::proto_encode(&450.0, &mut dst).unwrap();
+        assert_eq!(&dst, &vec![64, 192, 64]);
+        let value = <Angle as Protocol>::proto_decode(&mut io::Cursor::new(vec![192])).unwrap();
+        assert_eq!(value, 270.0);
+    }
+}
@@ -0,0 +1,206 @@
+//! MC Protocol map item data types.
+
+use std::io::prelude::*;
+
+use error::{Error, Result};
+use packet::Protocol;
+use types::{Arr, Var};
+
+/// An icon on a map, such as a player marker.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapIcon {
+    /// The rotation of the icon, from 0 to 15 in steps of 22.5 degrees.
+    pub direction: u8,
+    /// The icon type, from 0 to 15.
+    pub kind: u8,
+    pub x: i8,
+    pub z: i8
+}
+
+impl Protocol for MapIcon {
+    type Clean = MapIcon;
+
+    #[allow(unused_variables)]
+    fn proto_len(value: &MapIcon) -> usize { 3 }
+
+    fn proto_encode(value: &MapIcon, dst: &mut Write) -> Result<()> {
+        if value.direction > 0xf || value.kind > 0xf {
+            return Err(Error::invalid("invalid map icon", Some(format!("direction and type must be 0 to 15, found {} and {}", value.direction, value.kind))));
+        }
+        try!(<u8 as Protocol>::proto_encode(&(value.direction << 4 | value.kind), dst));
+        try!(<i8 as Protocol>::proto_encode(&value.x, dst));
+        try!(<i8 as Protocol>::proto_encode(&value.z, dst));
+        Ok(())
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<MapIcon> {
+        let direction_and_type = try!(<u8 as Protocol>::proto_decode(src));
+        Ok(MapIcon {
+            direction: direction_and_type >> 4,
+            kind: direction_and_type & 0xf,
+            x: try!(<i8 as Protocol>::proto_decode(src)),
+            z: try!(<i8 as Protocol>::proto_decode(src))
+        })
+    }
+}
+
+/// The side length of a map, in pixels.
+pub const MAP_SIZE: usize = 128;
+
+/// A rectangle of pixel colors on a map.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapData {
+    pub columns: u8,
+    pub rows: u8,
+    pub x: u8,
+    pub z: u8,
+    /// The colors of the rectangle, one row after another.
+    pub data: Vec<u8>
+}
+
+impl MapData {
+    /// The whole map, from its `MAP_SIZE * MAP_SIZE` colors.
+    pub fn full(pixels: &[u8]) -> MapData {
+        MapData::region(pixels, 0, 0, MAP_SIZE as u8, MAP_SIZE as u8)
+    }
+
+    /// A rectangle of the map, from all of its `MAP_SIZE * MAP_SIZE` colors.
+    ///
+    /// This is used to only send the part of a map which has changed. Panics if the rectangle
+    /// doesn't fit on the map.
+    pub fn region(pixels: &[u8], x: u8, z: u8, columns: u8, rows: u8) -> MapData {
+        assert_eq!(pixels.len(), MAP_SIZE * MAP_SIZE);
+        assert!(x as usize + columns as usize <= MAP_SIZE && z as usize + rows as usize <= MAP_SIZE);
+        let mut data = Vec::with_capacity(columns as usize * rows as usize);
+        for row in (z as usize..z as usize + rows as usize) {
+            let start = row * MAP_SIZE + x as usize;
+            for &pixel in pixels[start..start + columns as usize].iter() {
+                data.push(pixel);
+            }
+        }
+        MapData {
+            columns: columns,
+            rows: rows,
+            x: x,
+            z: z,
+            data: data
+        }
+    }
+}
+
+/// Fails unless `map` has as many colors as its size says, and isn't empty.
+fn check_size(map: &MapData) -> Result<()> {
+    if map.columns == 0 || map.rows == 0 || map.data.len() != map.columns as usize * map.rows as usize {
+        Err(Error::invalid("invalid map data", Some(format!("expected {} by {} colors, found {}", map.columns, map.rows, map.data.len()))))
+    } else {
+        Ok(())
+    }
+}
+
+/// Optional `MapData`, which is sent as a lone 0 byte for the number of columns when no pixels
+/// are updated.
+pub struct OptionalMapData;
+
+impl Protocol for OptionalMapData {
+    type Clean = Option<MapData>;
+
+    fn proto_len(value: &Option<MapData>) -> usize {
+        match *value {
+            Some(ref map) => 4 + <Arr<Var<i32>, u8> as Protocol>::proto_len(&map.data), // columns, rows, x, z, data
+            None => 1
+        }
+    }
+
+    fn proto_encode(value: &Option<MapData>, dst: &mut Write) -> Result<()> {
+        match *value {
+            Some(ref map) => {
+                try!(check_size(map));
+                try!(<u8 as Protocol>::proto_encode(&map.columns, dst));
+                try!(<u8 as Protocol>::proto_encode(&map.rows, dst));
+                try!(<u8 as Protocol>::proto_encode(&map.x, dst));
+                try!(<u8 as Protocol>::proto_encode(&map.z, dst));
+                try!(<Arr<Var<i32>, u8> as Protocol>::proto_encode(&map.data, dst));
+            }
+            None => { try!(<u8 as Protocol>::proto_encode(&0, dst)) }
+        }
+        Ok(())
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<Option<MapData>> {
+        let columns = try!(<u8 as Protocol>::proto_decode(src));
+        if columns == 0 {
+            return Ok(None);
+        }
+        let map = MapData {
+            columns: columns,
+            rows: try!(<u8 as Protocol>::proto_decode(src)),
+            x: try!(<u8 as Protocol>::proto_decode(src)),
+            z: try!(<u8 as Protocol>::proto_decode(src)),
+            data: try!(<Arr<Var<i32>, u8> as Protocol>::proto_decode(src))
+        };
+        // Decoded maps must be valid to encode again.
+        try!(check_size(&map));
+        Ok(Some(map))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use packet::Protocol;
+
+    #[test]
+    fn map_icon_encode() {
+        let icon = MapIcon { direction: 8, kind: 1, x: -3, z: 4 };
+        let mut dst = Vec::new();
+        <MapIcon as Protocol>::proto_encode(&icon, &mut dst).unwrap();
+        assert_eq!(&dst, &vec![0x81, 0xfd, 0x04]);
+        let value = <MapIcon as Protocol>::proto_decode(&mut io::Cursor::new(dst)).unwrap();
+        assert_eq!(value, icon);
+    }
+
+    #[test]
+    fn map_data_region() {
+        let pixels: Vec<u8> = (0..MAP_SIZE * MAP_SIZE).map(|i| (i % 256) as u8).collect();
+        let map = MapData::region(&pixels, 2, 1, 3, 2);
+        assert_eq!(&map.data, &vec![130, 131, 132, 2, 3, 4]);
+
+        let value = Some(map);
+        let bytes = vec![3, 2, 2, 1, 6, 130, 131, 132, 2, 3, 4];
+        let mut dst = Vec::new();
+        <OptionalMapData as Protocol>::proto_encode(&value, &mut dst).unwrap();
+        assert_eq!(&dst, &bytes);
+        assert_eq!(<OptionalMapData as Protocol>::proto_len(&value), bytes.len());
+        let decoded = <OptionalMapData as Protocol>::proto_decode(&mut io::Cursor::new(bytes)).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn map_data_full() {
+        let pixels = vec![5u8; MAP_SIZE * MAP_SIZE];
+        let map = MapData::full(&pixels);
+        assert_eq!((map.columns, map.rows, map.x, map.z), (128, 128, 0, 0));
+        assert_eq!(map.data, pixels);
+    }
+
+    #[test]
+    fn map_data_empty() {
+        let mut dst = Vec::new();
+        <OptionalMapData as Protocol>::proto_encode(&None, &mut dst).unwrap();
+        assert_eq!(&dst, &vec![0]);
+        assert!(<OptionalMapData as Protocol>::proto_decode(&mut io::Cursor::new(dst)).unwrap().is_none());
+    }
+
+    #[test]
+    fn map_data_bad_size() {
+        // No rows.
+        let bytes = vec![3, 0, 0, 0, 0];
+        assert!(<OptionalMapData as Protocol>::proto_decode(&mut io::Cursor::new(bytes)).is_err());
+        // Fewer colors than 2 by 2 pixels.
+        let bytes = vec![2, 2, 0, 0, 3, 1, 2, 3];
+        assert!(<OptionalMapData as Protocol>::proto_decode(&mut io::Cursor::new(bytes)).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! MC Protocol entity metadata data type.
+
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+use std::io::prelude::*;
+
+use error::{Error, Result};
+use packet::Protocol;
+use types::Slot;
+
+/// A single typed entity metadata value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetadataValue {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Float(f32),
+    String(String),
+    Slot(Option<Slot>),
+    Position([i32; 3]),
+    Rotation([f32; 3])
+}
+
+impl MetadataValue {
+    /// The type ID of this value, stored in the upper 3 bits of its header byte.
+    pub fn id(&self) -> u8 {
+        match *self {
+            MetadataValue::Byte(_)     => 0,
+            MetadataValue::Short(_)    => 1,
+            MetadataValue::Int(_)      => 2,
+            MetadataValue::Float(_)    => 3,
+            MetadataValue::String(_)   => 4,
+            MetadataValue::Slot(_)     => 5,
+            MetadataValue::Position(_) => 6,
+            MetadataValue::Rotation(_) => 7
+        }
+    }
+}
+
+/// Entity metadata, a dictionary of indices (0 to 31) to typed values.
+///
+/// Entries are encoded in index order, followed by a 0x7f terminator. Since that is also the
+/// header of a Float at index 31, such an entry can't be encoded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metadata {
+    entries: BTreeMap<u8, MetadataValue>
+}
+
+impl Metadata {
+    pub fn new() -> Metadata {
+        Metadata { entries: BTreeMap::new() }
+    }
+
+    /// Sets the value at `index`, returning the previous one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is above 31, since it couldn't be encoded.
+    pub fn insert(&mut self, index: u8, value: MetadataValue) -> Option<MetadataValue> {
+        assert!(index <= 0x1f, "metadata index must be 0 to 31, found {}", index);
+        self.entries.insert(index, value)
+    }
+
+    pub fn get(&self, index: u8) -> Option<&MetadataValue> {
+        self.entries.get(&index)
+    }
+
+    pub fn remove(&mut self, index: u8) -> Option<MetadataValue> {
+        self.entries.remove(&index)
+    }
+
+    pub fn iter(&self) -> btree_map::Iter<u8, MetadataValue> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+impl Protocol for Metadata {
+    type Clean = Metadata;
+
+    fn proto_len(value: &Metadata) -> usize {
+        let mut len = 1; // terminator
+        for (_, entry) in value.entries.iter() {
+            len += 1 + match *entry {
+                MetadataValue::Byte(_)           => 1,
+                MetadataValue::Short(_)          => 2,
+                MetadataValue::Int(_)            => 4,
+                MetadataValue::Float(_)          => 4,
+                MetadataValue::String(ref s)     => <String as Protocol>::proto_len(s),
+                MetadataValue::Slot(ref slot)    => <Option<Slot> as Protocol>::proto_len(slot),
+                MetadataValue::Position(_)       => 12,
+                MetadataValue::Rotation(_)       => 12
+            };
+        }
+        len
+    }
+
+    fn proto_encode(value: &Metadata, dst: &mut Write) -> Result<()> {
+        for (&index, entry) in value.entries.iter() {
+            let header = entry.id() << 5 | index;
+            // A Float at index 31 would be read back as the terminator.
+            if header == 0x7f {
+                return Err(Error::invalid("invalid metadata index", Some("a Float can't be stored at index 31".to_string())));
+            }
+            try!(<u8 as Protocol>::proto_encode(&header, dst));
+            match *entry {
+                MetadataValue::Byte(ref x)     => try!(<i8 as Protocol>::proto_encode(x, dst)),
+                MetadataValue::Short(ref x)    => try!(<i16 as Protocol>::proto_encode(x, dst)),
+                MetadataValue::Int(ref x)      => try!(<i32 as Protocol>::proto_encode(x, dst)),
+                MetadataValue::Float(ref x)    => try!(<f32 as Protocol>::proto_encode(x, dst)),
+                MetadataValue::String(ref x)   => try!(<String as Protocol>::proto_encode(x, dst)),
+                MetadataValue::Slot(ref x)     => try!(<Option<Slot> as Protocol>::proto_encode(x, dst)),
+                MetadataValue::Position(ref x) => try!(<[i32; 3] as Protocol>::proto_encode(x, dst)),
+                MetadataValue::Rotation(ref x) => try!(<[f32; 3] as Protocol>::proto_encode(x, dst))
+            }
+        }
+        try!(<u8 as Protocol>::proto_encode(&0x7f, dst));
+        Ok(())
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<Metadata> {
+        let mut metadata = Metadata::new();
+        loop {
+            let header = try!(<u8 as Protocol>::proto_decode(src));
+            if header == 0x7f {
+                return Ok(metadata);
+            }
+            let value = match header >> 5 {
+                0 => MetadataValue::Byte(try!(<i8 as Protocol>::proto_decode(src))),
+                1 => MetadataValue::Short(try!(<i16 as Protocol>::proto_decode(src))),
+                2 => MetadataValue::Int(try!(<i32 as Protocol>::proto_decode(src))),
+                3 => MetadataValue::Float(try!(<f32 as Protocol>::proto_decode(src))),
+                4 => MetadataValue::String(try!(<String as Protocol>::proto_decode(src))),
+                5 => MetadataValue::Slot(try!(<Option<Slot> as Protocol>::proto_decode(src))),
+                6 => MetadataValue::Position(try!(<[i32; 3] as Protocol>::proto_decode(src))),
+                _ => MetadataValue::Rotation(try!(<[f32; 3] as Protocol>::proto_decode(src)))
+            };
+            metadata.insert(header & 0x1f, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use packet::Protocol;
+
+    #[test]
+    fn metadata_roundtrip() {
+        let mut metadata = Metadata::new();
+        metadata.insert(0, MetadataValue::Byte(0x20));
+        metadata.insert(2, MetadataValue::String("Herobrine".to_string()));
+        metadata.insert(6, MetadataValue::Float(20.0));
+        metadata.insert(17, MetadataValue::Position([1, -2, 3]));
+
+        let bytes = vec![
+            0x00, 0x20,
+            0x82, 0x09, 0x48, 0x65, 0x72, 0x6f, 0x62, 0x72, 0x69, 0x6e, 0x65,
+            0x66, 0x41, 0xa0, 0x00, 0x00,
+            0xd1,
+                0x00, 0x00, 0x00, 0x01,
+                0xff, 0xff, 0xff, 0xfe,
+                0x00, 0x00, 0x00, 0x03,
+            0x7f
+        ];
+
+        // Test correct length.
+        assert_eq!(<Metadata as Protocol>::proto_len(&metadata), bytes.len());
+
+        // Test encoding.
+        let mut dst = Vec::new();
+        <Metadata as Protocol>::proto_encode(&metadata, &mut dst).unwrap();
+        assert_eq!(&dst, &bytes);
+
+        // Test decoding.
+        let mut src = io::Cursor::new(bytes);
+        let value = <Metadata as Protocol>::proto_decode(&mut src).unwrap();
+        assert_eq!(&value, &metadata);
+    }
+
+    #[test]
+    fn metadata_empty() {
+        let mut dst = Vec::new();
+        <Metadata as Protocol>::proto_encode(&Metadata::new(), &mut dst).unwrap();
+        assert_eq!(&dst, &vec![0x7f]);
+    }
+
+    #[test]
+    fn metadata_empty_slot_and_rotation() {
+        let mut metadata = Metadata::new();
+        metadata.insert(10, MetadataValue::Slot(None));
+        metadata.insert(11, MetadataValue::Rotation([0.0, 90.0, 0.0]));
+
+        let mut dst = Vec::new();
+        <Metadata as Protocol>::proto_encode(&metadata, &mut dst).unwrap();
+        assert_eq!(<Metadata as Protocol>::proto_len(&metadata), dst.len());
+        let value = <Metadata as Protocol>::proto_decode(&mut io::Cursor::new(dst)).unwrap();
+        assert_eq!(&value, &metadata);
+    }
+
+    #[test]
+    #[should_panic]
+    fn metadata_bad_index() {
+        let mut metadata = Metadata::new();
+        metadata.insert(32, MetadataValue::Int(1));
+    }
+
+    #[test]
+    fn metadata_terminator_header() {
+        let mut metadata = Metadata::new();
+        metadata.insert(31, MetadataValue::Float(1.0));
+        assert!(<Metadata as Protocol>::proto_encode(&metadata, &mut Vec::new()).is_err());
+
+        metadata.insert(31, MetadataValue::Byte(1));
+        let mut dst = Vec::new();
+        <Metadata as Protocol>::proto_encode(&metadata, &mut dst).unwrap();
+        assert_eq!(&dst, &vec![0x1f, 0x01, 0x7f]);
+    }
+}
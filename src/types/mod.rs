@@ -1,16 +1,29 @@
 mod arr;
+mod chat;
 pub mod consts;
 mod chunk;
+mod fixed;
+mod map;
+mod metadata;
 mod nbt;
+mod particle;
 mod pos;
 mod slot;
 mod string;
 mod uuid;
 mod varnum;
+mod window;
 
 pub use self::arr::Arr;
+pub use self::chat::{Chat, ChatContent, ClickEvent, Color, HoverEvent};
 pub use self::chunk::{Chunk, ChunkColumn};
-pub use self::nbt::{NbtBlob, NbtValue};
+pub use self::fixed::{Angle, Fixed};
+pub use self::map::{MapData, MapIcon, OptionalMapData, MAP_SIZE};
+pub use self::metadata::{Metadata, MetadataValue};
+pub use self::nbt::{NbtBlob, NbtValue, OptionalNbt};
+pub use self::particle::ParticleType;
 pub use self::pos::BlockPos;
 pub use self::slot::Slot;
+pub use self::uuid::UuidString;
 pub use self::varnum::Var;
+pub use self::window::WindowType;
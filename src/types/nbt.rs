@@ -1,6 +1,7 @@
 //! MC Named Binary Tag type.
 
 use std::collections::HashMap;
+use std::error::FromError;
 use std::io;
 use std::io::ErrorKind::InvalidInput;
 use std::iter::AdditiveIterator;
@@ -13,6 +14,8 @@ use flate2::Compression;
 use flate2::read::{GzDecoder, ZlibDecoder};
 use flate2::write::{GzEncoder, ZlibEncoder};
 
+use error;
+use limits;
 use packet::Protocol;
 use util::ReadExactExt;
 
@@ -155,7 +158,23 @@ impl NbtValue {
 
     /// Reads any valid `NbtValue` header (that is, a type ID and a title of
     /// arbitrary UTF-8 bytes) from an `io::Read` source.
-    pub fn read_header(mut src: &mut io::Read) -> io::Result<(u8, String)> {
+    pub fn read_header(src: &mut io::Read) -> io::Result<(u8, String)> {
+        NbtValue::header_from(src).map_err(|err| FromError::from_error(err))
+    }
+
+    /// Reads the payload of an `NbtValue` with a given type ID from an
+    /// `io::Read` source.
+    ///
+    /// Lists and compounds may be nested up to the depth given by
+    /// `limits::current()`, and arrays and lists may be as long as its
+    /// array length limit.
+    pub fn from_reader(id: u8, src: &mut io::Read) -> io::Result<NbtValue> {
+        NbtValue::payload_from(id, src, 0).map_err(|err| FromError::from_error(err))
+    }
+
+    /// Reads a header, reporting errors as protocol errors so that exceeded
+    /// limits keep their `error::ErrorKind`.
+    fn header_from(mut src: &mut io::Read) -> error::Result<(u8, String)> {
         let id = try!(src.read_u8());
         if id == 0x00 { return Ok((0x00, "".to_string())); }
         // Extract the name.
@@ -164,7 +183,7 @@ impl NbtValue {
             let bytes = try!(src.read_exact(name_len as usize));
             match String::from_utf8(bytes) {
                 Ok(v) => v,
-                Err(e) => return Err(io::Error::new(InvalidInput, "string is not UTF-8", Some(format!("{}", e))))
+                Err(e) => return Err(nbt_error(io::Error::new(InvalidInput, "string is not UTF-8", Some(format!("{}", e)))))
             }
         } else {
             "".to_string()
@@ -172,9 +191,14 @@ impl NbtValue {
         Ok((id, name))
     }
 
-    /// Reads the payload of an `NbtValue` with a given type ID from an
-    /// `io::Read` source.
-    pub fn from_reader(id: u8, mut src: &mut io::Read) -> io::Result<NbtValue> {
+    /// Reads the payload of an `NbtValue` found inside `depth` lists or
+    /// compounds.
+    fn payload_from(id: u8, mut src: &mut io::Read, depth: usize) -> error::Result<NbtValue> {
+        if id == 0x09 || id == 0x0a {
+            try!(limits::check("NBT depth", depth, limits::current().max_nbt_depth));
+        }
+        // Array and list lengths come from the input, so the vectors below
+        // grow as their elements are read instead of being preallocated.
         match id {
             0x01 => Ok(NbtValue::Byte(try!(src.read_i8()))),
             0x02 => Ok(NbtValue::Short(try!(src.read_i16::<BigEndian>()))),
@@ -183,8 +207,8 @@ impl NbtValue {
             0x05 => Ok(NbtValue::Float(try!(src.read_f32::<BigEndian>()))),
             0x06 => Ok(NbtValue::Double(try!(src.read_f64::<BigEndian>()))),
             0x07 => { // ByteArray
-                let len = try!(src.read_i32::<BigEndian>()) as usize;
-                let mut buf = Vec::with_capacity(len);
+                let len = try!(read_len(src));
+                let mut buf = Vec::new();
                 for _ in range(0, len) {
                     buf.push(try!(src.read_i8()));
                 }
@@ -195,41 +219,52 @@ impl NbtValue {
                 let bytes = try!(src.read_exact(len as usize));
                 match String::from_utf8(bytes) {
                     Ok(v)  => Ok(NbtValue::String(v)),
-                    Err(e) => return Err(io::Error::new(InvalidInput, "string is not UTF-8", Some(format!("{}", e))))
+                    Err(e) => return Err(nbt_error(io::Error::new(InvalidInput, "string is not UTF-8", Some(format!("{}", e)))))
                 }
             },
             0x09 => { // List
                 let id = try!(src.read_u8());
-                let len = try!(src.read_i32::<BigEndian>()) as usize;
-                let mut buf = Vec::with_capacity(len);
+                let len = try!(read_len(src));
+                let mut buf = Vec::new();
                 for _ in range(0, len) {
-                    buf.push(try!(NbtValue::from_reader(id, src)));
+                    buf.push(try!(NbtValue::payload_from(id, src, depth + 1)));
                 }
                 Ok(NbtValue::List(buf))
             },
             0x0a => { // Compound
                 let mut buf = HashMap::new();
                 loop {
-                    let (id, name) = try!(NbtValue::read_header(src));
+                    let (id, name) = try!(NbtValue::header_from(src));
                     if id == 0x00 { break; }
-                    let tag = try!(NbtValue::from_reader(id, src));
+                    let tag = try!(NbtValue::payload_from(id, src, depth + 1));
                     buf.insert(name, tag);
                 }
                 Ok(NbtValue::Compound(buf))
             },
             0x0b => { // IntArray
-                let len = try!(src.read_i32::<BigEndian>()) as usize;
-                let mut buf = Vec::with_capacity(len);
+                let len = try!(read_len(src));
+                let mut buf = Vec::new();
                 for _ in range(0, len) {
                     buf.push(try!(src.read_i32::<BigEndian>()));
                 }
                 Ok(NbtValue::IntArray(buf))
             },
-            _ => Err(io::Error::new(InvalidInput, "invalid NbtValue id", None))
+            _ => Err(nbt_error(io::Error::new(InvalidInput, "invalid NbtValue id", None)))
         }
     }
 }
 
+/// Reads the length of an array or list, which must not be negative or
+/// above the array length limit.
+fn read_len(mut src: &mut io::Read) -> error::Result<usize> {
+    let len = try!(src.read_i32::<BigEndian>());
+    if len < 0 {
+        return Err(nbt_error(io::Error::new(InvalidInput, "invalid length", Some(format!("length must not be negative, found {}", len)))));
+    }
+    try!(limits::check("array length", len as usize, limits::current().max_array_len));
+    Ok(len as usize)
+}
+
 /// An object in the Named Binary Tag (NBT) file format.
 ///
 /// This is essentially a map of names to `NbtValue`s, with an optional top-
@@ -267,16 +302,22 @@ impl NbtBlob {
     }
 
     /// Extracts an `NbtBlob` object from an `io::Read` source.
-    pub fn from_reader(mut src: &mut io::Read) -> io::Result<NbtBlob> {
+    pub fn from_reader(src: &mut io::Read) -> io::Result<NbtBlob> {
         let header = try!(NbtValue::read_header(src));
+        NbtBlob::from_header(header, src).map_err(|err| FromError::from_error(err))
+    }
+
+    /// Extracts the content of an `NbtBlob` object whose header has already
+    /// been read.
+    fn from_header(header: (u8, String), src: &mut io::Read) -> error::Result<NbtBlob> {
         // Although it would be possible to read NBT format files composed of
         // arbitrary objects using the current API, by convention all files
         // have a top-level Compound.
         if header.0 != 0x0a {
-            return Err(io::Error::new(InvalidInput, "invalid NBT file",
-                       Some(format!("root value must be a Compound (0x0a)"))));
+            return Err(nbt_error(io::Error::new(InvalidInput, "invalid NBT file",
+                       Some(format!("root value must be a Compound (0x0a)")))));
         }
-        let content = try!(NbtValue::from_reader(header.0, src));
+        let content = try!(NbtValue::payload_from(header.0, src, 0));
         Ok(NbtBlob { title: header.1, content: content })
     }
 
@@ -361,6 +402,24 @@ impl<'a> Index<&'a str> for NbtBlob {
     }
 }
 
+/// Reports errors from the NBT reader and writer as `error::ErrorKind::Nbt`.
+fn nbt_error(err: io::Error) -> error::Error {
+    error::Error::new(error::ErrorKind::Nbt(err))
+}
+
+/// Reads an NBT value from a reader which stops after the NBT size limit,
+/// failing with `error::ErrorKind::LimitExceeded` if the value needs more.
+fn read_limited<T, F>(src: &mut io::Read, read: F) -> error::Result<T>
+    where F: FnOnce(&mut io::Read) -> error::Result<T>
+{
+    let max = limits::current().max_nbt_size;
+    let mut src = io::Read::take(src, max as u64);
+    match read(&mut src) {
+        Err(_) if src.limit() == 0 => Err(error::Error::new(error::ErrorKind::LimitExceeded { limit: "NBT size", max: max })),
+        result => result
+    }
+}
+
 impl Protocol for NbtBlob {
     type Clean = NbtBlob;
 
@@ -368,12 +427,47 @@ impl Protocol for NbtBlob {
         value.len()
     }
 
-    fn proto_encode(value: &NbtBlob, mut dst: &mut io::Write) -> io::Result<()> {
-        value.write(dst)
+    fn proto_encode(value: &NbtBlob, mut dst: &mut io::Write) -> error::Result<()> {
+        value.write(dst).map_err(nbt_error)
     }
 
-    fn proto_decode(mut src: &mut io::Read) -> io::Result<NbtBlob> {
-        NbtBlob::from_reader(src)
+    fn proto_decode(src: &mut io::Read) -> error::Result<NbtBlob> {
+        read_limited(src, |src| {
+            let header = try!(NbtValue::header_from(src));
+            NbtBlob::from_header(header, src)
+        })
+    }
+}
+
+/// An optional `NbtBlob`, which is sent as a lone TAG_End byte when missing.
+pub struct OptionalNbt;
+
+impl Protocol for OptionalNbt {
+    type Clean = Option<NbtBlob>;
+
+    fn proto_len(value: &Option<NbtBlob>) -> usize {
+        match *value {
+            Some(ref nbt) => nbt.len(),
+            None => 1
+        }
+    }
+
+    fn proto_encode(value: &Option<NbtBlob>, mut dst: &mut io::Write) -> error::Result<()> {
+        match *value {
+            Some(ref nbt) => nbt.write(dst).map_err(nbt_error),
+            None => dst.write_u8(0x00).map_err(|err| FromError::from_error(err))
+        }
+    }
+
+    fn proto_decode(src: &mut io::Read) -> error::Result<Option<NbtBlob>> {
+        read_limited(src, |src| {
+            let header = try!(NbtValue::header_from(src));
+            if header.0 == 0x00 {
+                Ok(None)
+            } else {
+                NbtBlob::from_header(header, src).map(Some)
+            }
+        })
     }
 }
 
@@ -382,8 +476,12 @@ mod tests {
     use super::*;
 
     use std::collections::HashMap;
+    use std::default::Default;
     use std::io;
+    use std::iter;
 
+    use error::ErrorKind;
+    use limits::{self, Limits};
     use packet::Protocol;
 
     #[test]
@@ -569,4 +667,94 @@ mod tests {
         let gz_file = NbtBlob::from_gzip(&mut io::Cursor::new(gzip_dst)).unwrap();
         assert_eq!(&nbt, &gz_file);
     }
+
+    #[test]
+    fn nbt_optional_empty() {
+        let mut dst = Vec::new();
+        <OptionalNbt as Protocol>::proto_encode(&None, &mut dst).unwrap();
+        assert_eq!(&dst, &vec![0x00]);
+        assert_eq!(<OptionalNbt as Protocol>::proto_len(&None), 1);
+
+        let mut src = io::Cursor::new(dst);
+        assert!(<OptionalNbt as Protocol>::proto_decode(&mut src).unwrap().is_none());
+    }
+
+    #[test]
+    fn nbt_optional_nonempty() {
+        let mut nbt = NbtBlob::new("".to_string());
+        nbt.insert("Text1".to_string(), NbtValue::String("hello".to_string()));
+        let value = Some(nbt);
+
+        let mut dst = Vec::new();
+        <OptionalNbt as Protocol>::proto_encode(&value, &mut dst).unwrap();
+        assert_eq!(<OptionalNbt as Protocol>::proto_len(&value), dst.len());
+
+        let mut src = io::Cursor::new(dst);
+        let file = <OptionalNbt as Protocol>::proto_decode(&mut src).unwrap();
+        assert_eq!(&file, &value);
+    }
+
+    /// An unnamed root compound containing `depth` nested, empty compounds.
+    fn nested_compounds(depth: usize) -> Vec<u8> {
+        let mut bytes: Vec<u8> = iter::repeat(vec![0x0a, 0x00, 0x00]).take(depth + 1).flat_map(|tag| tag.into_iter()).collect();
+        bytes.extend(iter::repeat(0x00).take(depth + 1));
+        bytes
+    }
+
+    #[test]
+    fn nbt_depth_limit() {
+        let _limits = limits::scope(Limits { max_nbt_depth: 2, ..Default::default() });
+        let mut src = io::Cursor::new(nested_compounds(2));
+        assert!(<NbtBlob as Protocol>::proto_decode(&mut src).is_ok());
+        let mut src = io::Cursor::new(nested_compounds(3));
+        match <NbtBlob as Protocol>::proto_decode(&mut src) {
+            Err(err) => match err.kind {
+                ErrorKind::LimitExceeded { limit: "NBT depth", max: 2 } => {}
+                kind => panic!("unexpected error kind {:?}", kind)
+            },
+            Ok(_) => panic!("decoded NBT over the depth limit")
+        }
+    }
+
+    #[test]
+    fn nbt_array_length_limit() {
+        let _limits = limits::scope(Limits { max_array_len: 2, ..Default::default() });
+        let bytes = vec![0x0a, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 1, 2, 3, 0x00];
+        match <NbtBlob as Protocol>::proto_decode(&mut io::Cursor::new(bytes)) {
+            Err(err) => match err.kind {
+                ErrorKind::LimitExceeded { limit: "array length", max: 2 } => {}
+                kind => panic!("unexpected error kind {:?}", kind)
+            },
+            Ok(_) => panic!("decoded an NBT array over the length limit")
+        }
+    }
+
+    #[test]
+    fn nbt_long_list_header() {
+        // A list header claiming 2097152 lists, followed by a truncated first one.
+        let bytes = vec![0x0a, 0x00, 0x00, 0x09, 0x00, 0x00, 0x09, 0x00, 0x20, 0x00, 0x00, 0x0a];
+        assert!(<NbtBlob as Protocol>::proto_decode(&mut io::Cursor::new(bytes)).is_err());
+    }
+
+    #[test]
+    fn nbt_size_limit() {
+        let _limits = limits::scope(Limits { max_nbt_size: 4, ..Default::default() });
+        let mut src = io::Cursor::new(nested_compounds(0));
+        assert!(<NbtBlob as Protocol>::proto_decode(&mut src).is_ok());
+        let mut src = io::Cursor::new(nested_compounds(1));
+        match <OptionalNbt as Protocol>::proto_decode(&mut src) {
+            Err(err) => match err.kind {
+                ErrorKind::LimitExceeded { limit: "NBT size", max: 4 } => {}
+                kind => panic!("unexpected error kind {:?}", kind)
+            },
+            Ok(_) => panic!("decoded NBT over the size limit")
+        }
+    }
+
+    #[test]
+    fn nbt_negative_length() {
+        let bytes = vec![0x0a, 0x00, 0x00, 0x07, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00];
+        let mut src = io::Cursor::new(bytes);
+        assert!(<NbtBlob as Protocol>::proto_decode(&mut src).is_err());
+    }
 }
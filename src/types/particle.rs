@@ -0,0 +1,199 @@
+//! MC Protocol particle types.
+
+use std::io::prelude::*;
+use std::iter::AdditiveIterator;
+
+use error::{Error, ErrorKind, Result};
+use packet::Protocol;
+use types::Var;
+
+/// A particle type, along with the data some particles are sent with.
+///
+/// For potion effects, `MobSpell` and `MobSpellAmbient` particles take their color from the
+/// offset of the `Particle` packet, with a count of 0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParticleType {
+    Explode,
+    LargeExplode,
+    HugeExplosion,
+    FireworksSpark,
+    Bubble,
+    Splash,
+    Wake,
+    Suspended,
+    DepthSuspend,
+    Crit,
+    MagicCrit,
+    Smoke,
+    LargeSmoke,
+    Spell,
+    InstantSpell,
+    MobSpell,
+    MobSpellAmbient,
+    WitchMagic,
+    DripWater,
+    DripLava,
+    AngryVillager,
+    HappyVillager,
+    TownAura,
+    Note,
+    Portal,
+    EnchantmentTable,
+    Flame,
+    Lava,
+    Footstep,
+    Cloud,
+    RedDust,
+    SnowballPoof,
+    SnowShovel,
+    Slime,
+    Heart,
+    Barrier,
+    /// An item breaking, given as item ID and damage.
+    IconCrack(i32, i32),
+    /// A block breaking, given as block ID and metadata.
+    BlockCrack(i32, i32),
+    /// Dust from a block, given as block ID and metadata.
+    BlockDust(i32, i32),
+    Droplet,
+    Take,
+    MobAppearance
+}
+
+/// Particles without extra data, in ID order.
+static SIMPLE_PARTICLES: [ParticleType; 36] = [
+    ParticleType::Explode,
+    ParticleType::LargeExplode,
+    ParticleType::HugeExplosion,
+    ParticleType::FireworksSpark,
+    ParticleType::Bubble,
+    ParticleType::Splash,
+    ParticleType::Wake,
+    ParticleType::Suspended,
+    ParticleType::DepthSuspend,
+    ParticleType::Crit,
+    ParticleType::MagicCrit,
+    ParticleType::Smoke,
+    ParticleType::LargeSmoke,
+    ParticleType::Spell,
+    ParticleType::InstantSpell,
+    ParticleType::MobSpell,
+    ParticleType::MobSpellAmbient,
+    ParticleType::WitchMagic,
+    ParticleType::DripWater,
+    ParticleType::DripLava,
+    ParticleType::AngryVillager,
+    ParticleType::HappyVillager,
+    ParticleType::TownAura,
+    ParticleType::Note,
+    ParticleType::Portal,
+    ParticleType::EnchantmentTable,
+    ParticleType::Flame,
+    ParticleType::Lava,
+    ParticleType::Footstep,
+    ParticleType::Cloud,
+    ParticleType::RedDust,
+    ParticleType::SnowballPoof,
+    ParticleType::SnowShovel,
+    ParticleType::Slime,
+    ParticleType::Heart,
+    ParticleType::Barrier
+];
+
+impl ParticleType {
+    /// The particle ID, sent at the start of the `Particle` packet.
+    pub fn id(&self) -> i32 {
+        match *self {
+            ParticleType::IconCrack(..) => 36,
+            ParticleType::BlockCrack(..) => 37,
+            ParticleType::BlockDust(..) => 38,
+            ParticleType::Droplet => 39,
+            ParticleType::Take => 40,
+            ParticleType::MobAppearance => 41,
+            particle => SIMPLE_PARTICLES.iter().position(|&p| p == particle).unwrap() as i32
+        }
+    }
+
+    /// The VarInts sent at the end of the `Particle` packet.
+    ///
+    /// Block IDs are combined with their metadata as `id | metadata << 12`.
+    pub fn data(&self) -> Vec<i32> {
+        match *self {
+            ParticleType::IconCrack(id, damage) => vec![id, damage],
+            ParticleType::BlockCrack(id, metadata) |
+            ParticleType::BlockDust(id, metadata) => vec![id | metadata << 12],
+            _ => vec![]
+        }
+    }
+
+    pub fn data_len(&self) -> usize {
+        self.data().iter().map(|x| <Var<i32> as Protocol>::proto_len(x)).sum()
+    }
+
+    pub fn encode_data(&self, dst: &mut Write) -> Result<()> {
+        for x in self.data().iter() {
+            try!(<Var<i32> as Protocol>::proto_encode(x, dst));
+        }
+        Ok(())
+    }
+
+    /// Reads the data for the particle with the given ID.
+    pub fn decode_data(id: i32, src: &mut Read) -> Result<ParticleType> {
+        match id {
+            0...35 => Ok(SIMPLE_PARTICLES[id as usize]),
+            36 => {
+                let item = try!(<Var<i32> as Protocol>::proto_decode(src));
+                let damage = try!(<Var<i32> as Protocol>::proto_decode(src));
+                Ok(ParticleType::IconCrack(item, damage))
+            }
+            37 | 38 => {
+                let block = try!(<Var<i32> as Protocol>::proto_decode(src));
+                let (id_, metadata) = (block & 0xfff, block >> 12);
+                Ok(if id == 37 { ParticleType::BlockCrack(id_, metadata) } else { ParticleType::BlockDust(id_, metadata) })
+            }
+            39 => Ok(ParticleType::Droplet),
+            40 => Ok(ParticleType::Take),
+            41 => Ok(ParticleType::MobAppearance),
+            _ => Err(Error::new(ErrorKind::InvalidEnumValue { name: "ParticleType", value: id as i64 }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    #[test]
+    fn particle_ids() {
+        assert_eq!(ParticleType::Explode.id(), 0);
+        assert_eq!(ParticleType::Barrier.id(), 35);
+        assert_eq!(ParticleType::BlockDust(1, 0).id(), 38);
+        assert_eq!(ParticleType::MobAppearance.id(), 41);
+    }
+
+    #[test]
+    fn particle_data_roundtrip() {
+        let particles = vec![
+            ParticleType::Heart,
+            ParticleType::IconCrack(276, 3),
+            ParticleType::BlockCrack(35, 14),
+            ParticleType::BlockDust(1, 0)
+        ];
+        for particle in particles.into_iter() {
+            let mut dst = Vec::new();
+            particle.encode_data(&mut dst).unwrap();
+            assert_eq!(dst.len(), particle.data_len());
+            let decoded = ParticleType::decode_data(particle.id(), &mut io::Cursor::new(dst)).unwrap();
+            assert_eq!(decoded, particle);
+        }
+    }
+
+    #[test]
+    fn particle_block_data() {
+        let mut dst = Vec::new();
+        ParticleType::BlockCrack(35, 14).encode_data(&mut dst).unwrap();
+        assert_eq!(&dst, &vec![0xa3, 0xc0, 0x03]);
+    }
+}
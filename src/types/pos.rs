@@ -1,9 +1,9 @@
 //! 3D position types
 
-use std::io;
 use std::io::prelude::*;
 use std::iter::AdditiveIterator;
 
+use error::{Error, Result};
 use packet::Protocol;
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
@@ -13,7 +13,7 @@ pub struct BlockPos;
 macro_rules! bounds_check {
     ($name:expr, $value:expr, $size:expr) => {
         if $value < -(1 << $size) || $value >= (1 << $size) {
-            return Err(io::Error::new(io::ErrorKind::InvalidInput, "coordinate out of bounds", Some(format!("expected {} to {}, found {} for {} coord", -(1 << $size), (1 << $size) - 1, $value, $name))));
+            return Err(Error::invalid("coordinate out of bounds", Some(format!("expected {} to {}, found {} for {} coord", -(1 << $size), (1 << $size) - 1, $value, $name))));
         }
     }
 }
@@ -24,7 +24,7 @@ impl Protocol for BlockPos {
     #[allow(unused_variables)]
     fn proto_len(value: &[i32; 3]) -> usize { 8 }
 
-    fn proto_encode(value: &[i32; 3], mut dst: &mut Write) -> io::Result<()> {
+    fn proto_encode(value: &[i32; 3], mut dst: &mut Write) -> Result<()> {
         let x = value[0].clone();
         let y = value[1].clone();
         let z = value[2].clone();
@@ -35,7 +35,7 @@ impl Protocol for BlockPos {
         Ok(())
     }
 
-    fn proto_decode(mut src: &mut Read) -> io::Result<[i32; 3]> {
+    fn proto_decode(mut src: &mut Read) -> Result<[i32; 3]> {
         let block_pos = try!(src.read_u64::<BigEndian>());
         let x = (block_pos >> 38) as i32;
         let y = ((block_pos >> 26) & 0xfff) as i32;
@@ -51,14 +51,14 @@ impl<T: Protocol> Protocol for [T; 3] {
         value.iter().map(|coord| <T as Protocol>::proto_len(coord)).sum()
     }
 
-    fn proto_encode(value: &[T::Clean; 3], dst: &mut Write) -> io::Result<()> {
+    fn proto_encode(value: &[T::Clean; 3], dst: &mut Write) -> Result<()> {
         for coord in value.iter() {
             try!(<T as Protocol>::proto_encode(coord, dst));
         }
         Ok(())
     }
 
-    fn proto_decode(src: &mut Read) -> io::Result<[T::Clean; 3]> {
+    fn proto_decode(src: &mut Read) -> Result<[T::Clean; 3]> {
         let x = try!(<T as Protocol>::proto_decode(src));
         let y = try!(<T as Protocol>::proto_decode(src));
         let z = try!(<T as Protocol>::proto_decode(src));
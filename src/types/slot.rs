@@ -1,17 +1,17 @@
 //! Minecraft item stack (inventory slot) data type
 
-use std::io;
 use std::io::prelude::*;
 
+use error::Result;
 use packet::Protocol;
-use types::NbtBlob;
+use types::{NbtBlob, OptionalNbt};
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Slot {
     id: u16,
     count: u8,
     damage: i16,
-    tag: NbtBlob
+    tag: Option<NbtBlob>
 }
 
 impl Protocol for Option<Slot> {
@@ -19,25 +19,25 @@ impl Protocol for Option<Slot> {
 
     fn proto_len(value: &Option<Slot>) -> usize {
         match *value {
-            Some(ref slot) => 2 + 1 + 2 + <NbtBlob as Protocol>::proto_len(&slot.tag), // id, count, damage, tag
+            Some(ref slot) => 2 + 1 + 2 + <OptionalNbt as Protocol>::proto_len(&slot.tag), // id, count, damage, tag
             None => 2
         }
     }
 
-    fn proto_encode(value: &Option<Slot>, dst: &mut Write) -> io::Result<()> {
+    fn proto_encode(value: &Option<Slot>, dst: &mut Write) -> Result<()> {
         match *value {
             Some(Slot { id, count, damage, ref tag }) => {
                 try!(<i16 as Protocol>::proto_encode(&(id as i16), dst));
                 try!(<u8 as Protocol>::proto_encode(&count, dst));
                 try!(<i16 as Protocol>::proto_encode(&damage, dst));
-                try!(<NbtBlob as Protocol>::proto_encode(tag, dst));
+                try!(<OptionalNbt as Protocol>::proto_encode(tag, dst).map_err(|err| err.in_field("tag")));
             }
             None => { try!(<i16 as Protocol>::proto_encode(&-1, dst)) }
         }
         Ok(())
     }
 
-    fn proto_decode(src: &mut Read) -> io::Result<Option<Slot>> {
+    fn proto_decode(src: &mut Read) -> Result<Option<Slot>> {
         let id = try!(<i16 as Protocol>::proto_decode(src));
         Ok(if id == -1 {
             None
@@ -46,7 +46,7 @@ impl Protocol for Option<Slot> {
                 id: id as u16,
                 count: try!(<u8 as Protocol>::proto_decode(src)),
                 damage: try!(<i16 as Protocol>::proto_decode(src)),
-                tag: try!(<NbtBlob as Protocol>::proto_decode(src))
+                tag: try!(<OptionalNbt as Protocol>::proto_decode(src).map_err(|err| err.in_field("tag")))
             })
         })
     }
@@ -1,13 +1,28 @@
 //! MC Protocol String data type.
 
-use std::error::Error;
-use std::io;
 use std::io::prelude::*;
 
+use error::{Error, ErrorKind, Result};
+use limits;
 use packet::Protocol;
 use types::Var;
 use util::ReadExactExt;
 
+/// The maximum length of a string allowed by the protocol, in characters.
+///
+/// Strings are decoded up to the length in `limits::current()` instead.
+pub const MAX_STRING_LEN: usize = 32767;
+
+/// Fails if `value` has more than `max` characters.
+fn check_len(value: &str, max: usize) -> Result<()> {
+    let len = value.chars().count();
+    if len > max {
+        Err(Error::new(ErrorKind::StringTooLong { len: len, max: max }))
+    } else {
+        Ok(())
+    }
+}
+
 /// UTF-8 string prefixed with its length as a VarInt.
 impl Protocol for String {
     type Clean = String;
@@ -17,16 +32,25 @@ impl Protocol for String {
         <Var<i32> as Protocol>::proto_len(&(str_len as i32)) + str_len
     }
 
-    fn proto_encode(value: &String, dst: &mut Write) -> io::Result<()> {
+    fn proto_encode(value: &String, dst: &mut Write) -> Result<()> {
+        try!(check_len(value, MAX_STRING_LEN));
         let str_len = value.len() as i32;
         try!(<Var<i32> as Protocol>::proto_encode(&str_len, dst));
         try!(dst.write_all(value.as_bytes()));
         Ok(())
     }
 
-    fn proto_decode(mut src: &mut Read) -> io::Result<String> {
+    fn proto_decode(mut src: &mut Read) -> Result<String> {
         let len: i32 = try!(<Var<i32> as Protocol>::proto_decode(src));
+        if len < 0 {
+            return Err(Error::invalid("invalid String length", Some(format!("length must not be negative, found {}", len))));
+        }
+        let max = limits::current().max_string_len;
+        // Each character takes up at most 4 bytes.
+        try!(limits::check("string length in bytes", len as usize, max * 4));
         let s = try!(src.read_exact(len as usize));
-        String::from_utf8(s).map_err(|utf8_err| io::Error::new(io::ErrorKind::InvalidInput, "invalid String value", Some(format!("UTF-8 error: {}", utf8_err.utf8_error().description()))))
+        let s = try!(String::from_utf8(s).map_err(|_| Error::new(ErrorKind::BadUtf8)));
+        try!(check_len(&s, max));
+        Ok(s)
     }
 }
@@ -1,8 +1,8 @@
 //! MC Protocol UUID data type.
 
-use std::io;
 use std::io::prelude::*;
 
+use error::{Error, Result};
 use packet::Protocol;
 use util::ReadExactExt;
 
@@ -16,15 +16,65 @@ impl Protocol for Uuid {
     fn proto_len(value: &Uuid) -> usize { 16 }
 
     /// Writes `value` into `dst`
-    fn proto_encode(value: &Uuid, dst: &mut Write) -> io::Result<()> {
-        dst.write_all(value.as_bytes())
+    fn proto_encode(value: &Uuid, dst: &mut Write) -> Result<()> {
+        try!(dst.write_all(value.as_bytes()));
+        Ok(())
     }
 
     /// Reads 16 bytes from `src` and returns a `Uuid`
     #[allow(unused_variables)]
-    fn proto_decode(mut src: &mut Read) -> io::Result<Uuid> {
+    fn proto_decode(mut src: &mut Read) -> Result<Uuid> {
         let v = try!(src.read_exact(16));
-        Uuid::from_bytes(&v).ok_or(io::Error::new(io::ErrorKind::InvalidInput, "invalid UUID value", Some(format!("value {:?} can't be used to create UUID", v))))
+        Uuid::from_bytes(&v).ok_or(Error::invalid("invalid UUID value", Some(format!("value {:?} can't be used to create UUID", v))))
     }
 }
 
+/// UUID sent as a hyphenated string, as in `LoginSuccess`.
+#[derive(Debug)]
+pub struct UuidString;
+
+impl Protocol for UuidString {
+    type Clean = Uuid;
+
+    fn proto_len(value: &Uuid) -> usize {
+        <String as Protocol>::proto_len(&value.to_hyphenated_string())
+    }
+
+    /// Writes `value` into `dst` as a hyphenated string
+    fn proto_encode(value: &Uuid, dst: &mut Write) -> Result<()> {
+        <String as Protocol>::proto_encode(&value.to_hyphenated_string(), dst)
+    }
+
+    /// Reads a string from `src` and parses it as a `Uuid`
+    fn proto_decode(src: &mut Read) -> Result<Uuid> {
+        let s = try!(<String as Protocol>::proto_decode(src));
+        Uuid::parse_str(&s).map_err(|_| Error::invalid("invalid UUID string", Some(format!("{:?} is not a valid UUID", s))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use packet::Protocol;
+    use types::uuid::UuidString;
+    use uuid::Uuid;
+
+    #[test]
+    fn uuid_string() {
+        let text = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+        let uuid = Uuid::parse_str(text).unwrap();
+        let mut dst = Vec::new();
+        <UuidString as Protocol>::proto_encode(&uuid, &mut dst).unwrap();
+        assert_eq!(dst[0], 36);
+        assert_eq!(&dst[1..], text.as_bytes());
+        assert_eq!(<UuidString as Protocol>::proto_len(&uuid), 37);
+        assert_eq!(<UuidString as Protocol>::proto_decode(&mut io::Cursor::new(dst)).unwrap(), uuid);
+    }
+
+    #[test]
+    fn bad_uuid_string() {
+        let mut src = io::Cursor::new(vec![3, 0x61, 0x62, 0x63]);
+        assert!(<UuidString as Protocol>::proto_decode(&mut src).is_err());
+    }
+}
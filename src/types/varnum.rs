@@ -2,11 +2,11 @@
 
 use byteorder::{ReadBytesExt, WriteBytesExt};
 
-use std::io;
 use std::io::prelude::*;
 use std::iter::range_step;
 use std::marker::PhantomData;
 
+use error::{Error, Result};
 use packet::Protocol;
 
 /// Protocol Buffer varint.
@@ -27,7 +27,7 @@ impl Protocol for Var<i32> {
     }
 
     /// Writes `value` as a VarInt into `dst`, it can be up to 5 bytes.
-    fn proto_encode(value: &i32, mut dst: &mut Write) -> io::Result<()> {
+    fn proto_encode(value: &i32, mut dst: &mut Write) -> Result<()> {
         let mut temp = *value as u32;
         loop {
             if (temp & !0x7fu32) == 0 {
@@ -42,7 +42,7 @@ impl Protocol for Var<i32> {
 
     /// Reads up to 5 bytes from `src`, until a valid `Var<i32>` is found.
     #[allow(unused_variables)]
-    fn proto_decode(mut src: &mut Read) -> io::Result<i32> {
+    fn proto_decode(mut src: &mut Read) -> Result<i32> {
         let mut x = 0i32;
 
         for shift in range_step(0, 32, 7) {
@@ -54,7 +54,7 @@ impl Protocol for Var<i32> {
         }
 
         // The number is too large to represent in a 32-bit value.
-        Err(io::Error::new(io::ErrorKind::InvalidInput, "VarInt too big", None))
+        Err(Error::invalid("VarInt too big", None))
     }
 }
 
@@ -73,7 +73,7 @@ impl Protocol for Var<i64> {
     }
 
     /// Writes `value` as a VarLong into `dst`, it can be up to 10 bytes.
-    fn proto_encode(value: &i64, mut dst: &mut Write) -> io::Result<()> {
+    fn proto_encode(value: &i64, mut dst: &mut Write) -> Result<()> {
         let mut temp = *value as u64;
         loop {
             if (temp & !0x7fu64) == 0 {
@@ -88,7 +88,7 @@ impl Protocol for Var<i64> {
 
     /// Reads up to 10 bytes from `dst`, until a valid `Var<i64>` is found.
     #[allow(unused_variables)]
-    fn proto_decode(mut dst: &mut Read) -> io::Result<i64> {
+    fn proto_decode(mut dst: &mut Read) -> Result<i64> {
         let mut x = 0i64;
 
         for shift in range_step(0, 64, 7) {
@@ -100,7 +100,7 @@ impl Protocol for Var<i64> {
         }
 
         // The number is too large to represent in a 64-bit value.
-        Err(io::Error::new(io::ErrorKind::InvalidInput, "VarLong too big", None))
+        Err(Error::invalid("VarLong too big", None))
     }
 }
 
@@ -0,0 +1,60 @@
+//! MC Protocol window types.
+
+/// The kind of window opened by the `OpenWindow` packet.
+///
+/// Horse inventories are the only windows which belong to an entity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WindowType {
+    Chest,
+    CraftingTable,
+    Furnace,
+    Dispenser,
+    EnchantingTable,
+    BrewingStand,
+    Villager,
+    Beacon,
+    Anvil,
+    Hopper,
+    Dropper,
+    /// A horse inventory, given as the horse's entity ID.
+    EntityHorse(i32)
+}
+
+impl WindowType {
+    /// The window type string sent in the `OpenWindow` packet.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            WindowType::Chest           => "minecraft:chest",
+            WindowType::CraftingTable   => "minecraft:crafting_table",
+            WindowType::Furnace         => "minecraft:furnace",
+            WindowType::Dispenser       => "minecraft:dispenser",
+            WindowType::EnchantingTable => "minecraft:enchanting_table",
+            WindowType::BrewingStand    => "minecraft:brewing_stand",
+            WindowType::Villager        => "minecraft:villager",
+            WindowType::Beacon          => "minecraft:beacon",
+            WindowType::Anvil           => "minecraft:anvil",
+            WindowType::Hopper          => "minecraft:hopper",
+            WindowType::Dropper         => "minecraft:dropper",
+            WindowType::EntityHorse(_)  => "EntityHorse"
+        }
+    }
+
+    /// Looks up a window type by name, with `entity_id` only used for horses.
+    pub fn from_name(name: &str, entity_id: i32) -> Option<WindowType> {
+        match name {
+            "minecraft:chest"            => Some(WindowType::Chest),
+            "minecraft:crafting_table"   => Some(WindowType::CraftingTable),
+            "minecraft:furnace"          => Some(WindowType::Furnace),
+            "minecraft:dispenser"        => Some(WindowType::Dispenser),
+            "minecraft:enchanting_table" => Some(WindowType::EnchantingTable),
+            "minecraft:brewing_stand"    => Some(WindowType::BrewingStand),
+            "minecraft:villager"         => Some(WindowType::Villager),
+            "minecraft:beacon"           => Some(WindowType::Beacon),
+            "minecraft:anvil"            => Some(WindowType::Anvil),
+            "minecraft:hopper"           => Some(WindowType::Hopper),
+            "minecraft:dropper"          => Some(WindowType::Dropper),
+            "EntityHorse"                => Some(WindowType::EntityHorse(entity_id)),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,509 @@
+//! Packet tables for protocol versions other than the one in `packet`.
+//!
+//! The packets declared in `packet` use the IDs and layouts of 1.8 (protocol 47), and act as
+//! the canonical representation for every version. Other versions map their own packet IDs
+//! onto these packets, optionally with different field types for packets whose layout
+//! changed, so that the rest of a server only deals with one set of packets.
+
+use std::any::Any;
+use std::default::Default;
+use std::io::prelude::*;
+use std::marker::PhantomData;
+
+use error::{Error, Result};
+use packet::{self, Direction, Packet, PacketEnum, Protocol, State};
+
+/// The height of a player's eyes above their feet, which protocol 5 sends in some positions.
+const EYE_HEIGHT: f64 = 1.62;
+
+/// The bit of the cape in `displayed_skin_parts`, and the bits of all skin parts.
+const SKIN_CAPE: u8 = 0x01;
+const SKIN_ALL: u8 = 0x7f;
+
+/// A field which a version doesn't have. It is dropped when encoding, and decodes to its
+/// default value.
+pub struct Omitted<T>(PhantomData<fn() -> T>);
+
+impl<T: Default> Protocol for Omitted<T> {
+    type Clean = T;
+
+    #[allow(unused_variables)]
+    fn proto_len(value: &T) -> usize { 0 }
+
+    #[allow(unused_variables)]
+    fn proto_encode(value: &T, dst: &mut Write) -> Result<()> {
+        Ok(())
+    }
+
+    #[allow(unused_variables)]
+    fn proto_decode(src: &mut Read) -> Result<T> {
+        Ok(Default::default())
+    }
+}
+
+/// A position sent with the height of the eyes instead of the feet, as in the clientbound
+/// `PlayerPositionAndLook` of protocol 5.
+pub struct EyePosition;
+
+impl Protocol for EyePosition {
+    type Clean = [f64; 3];
+
+    #[allow(unused_variables)]
+    fn proto_len(value: &[f64; 3]) -> usize { 24 }
+
+    fn proto_encode(value: &[f64; 3], dst: &mut Write) -> Result<()> {
+        <[f64; 3] as Protocol>::proto_encode(&[value[0], value[1] + EYE_HEIGHT, value[2]], dst)
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<[f64; 3]> {
+        let position = try!(<[f64; 3] as Protocol>::proto_decode(src));
+        Ok([position[0], position[1] - EYE_HEIGHT, position[2]])
+    }
+}
+
+/// A position sent with the height of the eyes (the "stance") after that of the feet, as in
+/// the serverbound position packets of protocol 5. The stance is not checked when decoding.
+pub struct StancePosition;
+
+impl Protocol for StancePosition {
+    type Clean = [f64; 3];
+
+    #[allow(unused_variables)]
+    fn proto_len(value: &[f64; 3]) -> usize { 32 }
+
+    fn proto_encode(value: &[f64; 3], dst: &mut Write) -> Result<()> {
+        for coord in [value[0], value[1], value[1] + EYE_HEIGHT, value[2]].iter() {
+            try!(<f64 as Protocol>::proto_encode(coord, dst));
+        }
+        Ok(())
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<[f64; 3]> {
+        let x = try!(<f64 as Protocol>::proto_decode(src));
+        let y = try!(<f64 as Protocol>::proto_decode(src));
+        try!(<f64 as Protocol>::proto_decode(src));
+        let z = try!(<f64 as Protocol>::proto_decode(src));
+        Ok([x, y, z])
+    }
+}
+
+/// The `flags` of the clientbound `PlayerPositionAndLook`, which protocol 5 replaces with an
+/// `on_ground` flag. Since relative positions don't exist there, the flags must be 0.
+pub struct OnGroundFlags;
+
+impl Protocol for OnGroundFlags {
+    type Clean = i8;
+
+    #[allow(unused_variables)]
+    fn proto_len(value: &i8) -> usize { 1 }
+
+    fn proto_encode(value: &i8, dst: &mut Write) -> Result<()> {
+        if *value != 0 {
+            return Err(Error::invalid("relative position not supported by protocol version", Some(format!("flags must be 0, found {:#x}", value))));
+        }
+        <bool as Protocol>::proto_encode(&false, dst)
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<i8> {
+        try!(<bool as Protocol>::proto_decode(src));
+        Ok(0)
+    }
+}
+
+/// The `displayed_skin_parts` of `ClientSettings`, which protocol 5 sends as the difficulty
+/// followed by a `show_cape` flag. The difficulty was never used by servers, and is sent as
+/// peaceful. The cape is the only part which could be hidden, so the others are shown.
+pub struct CapeSkinParts;
+
+impl Protocol for CapeSkinParts {
+    type Clean = u8;
+
+    #[allow(unused_variables)]
+    fn proto_len(value: &u8) -> usize { 2 }
+
+    fn proto_encode(value: &u8, dst: &mut Write) -> Result<()> {
+        try!(<u8 as Protocol>::proto_encode(&0, dst));
+        <bool as Protocol>::proto_encode(&(*value & SKIN_CAPE != 0), dst)
+    }
+
+    fn proto_decode(src: &mut Read) -> Result<u8> {
+        try!(<u8 as Protocol>::proto_decode(src));
+        let show_cape = try!(<bool as Protocol>::proto_decode(src));
+        Ok(if show_cape { SKIN_ALL } else { SKIN_ALL & !SKIN_CAPE })
+    }
+}
+
+/// Decodes a canonical packet from the body of a translated one.
+macro_rules! translate_decode {
+    ($state_mod:ident, $dir:ident, $name:ident, $body:expr, {}) => {
+        decode_body_with(stringify!($name), $body, <::packet::$state_mod::$dir::$name as Packet>::decode)
+    };
+    ($state_mod:ident, $dir:ident, $name:ident, $body:expr, { $($fname:ident: $fty:ty),+ }) => {
+        decode_body_with(stringify!($name), $body, |src: &mut Read, _: usize| {
+            Ok(::packet::$state_mod::$dir::$name {
+                $($fname: try!(decode_field::<$fty>(stringify!($name), stringify!($fname), src))),+
+            })
+        })
+    }
+}
+
+/// Encodes a canonical packet with the layout of a translated one.
+macro_rules! translate_encode {
+    ($name:ident, $packet:ident, $dst:ident, {}) => {
+        $packet.encode(&mut $dst).map_err(|err| err.in_packet(stringify!($name)))
+    };
+    ($name:ident, $packet:ident, $dst:ident, { $($fname:ident: $fty:ty),+ }) => {{
+        $(try!(<$fty as Protocol>::proto_encode(&$packet.$fname, &mut $dst).map_err(|err| err.in_field(stringify!($fname)).in_packet(stringify!($name))));)+
+        Ok(())
+    }}
+}
+
+/// Declares the packet tables of older protocol versions.
+///
+/// Each version lists all four states. A packet is given as `id => Name {}` if its layout is
+/// the same as in protocol 47, or with the full list of its fields and their types otherwise.
+/// Fields which changed in more than their type use a type converting them, such as `Omitted`
+/// for fields the version doesn't have. Packets which are missing from a version can't be read
+/// or written with it.
+macro_rules! versions {
+    ($($version:ident ($proto:expr) => $vmod:ident {
+        $($state:ident => $state_mod:ident {
+            clientbound { $($c_id:expr => $c_name:ident { $($c_fields:tt)* })* }
+            serverbound { $($s_id:expr => $s_name:ident { $($s_fields:tt)* })* }
+        })*
+    })*) => {
+        /// A protocol version, identified by the `proto_version` of the `Handshake` packet.
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub enum Version {
+            $($version,)*
+            V47
+        }
+
+        impl Version {
+            /// Returns the version with the given protocol number, if it is supported.
+            pub fn from_protocol(proto_version: i32) -> Option<Version> {
+                match proto_version {
+                    $($proto => Some(Version::$version),)*
+                    47 => Some(Version::V47),
+                    _ => None
+                }
+            }
+
+            /// The protocol number sent in the `Handshake` packet.
+            pub fn protocol(&self) -> i32 {
+                match *self {
+                    $(Version::$version => $proto,)*
+                    Version::V47 => 47
+                }
+            }
+
+            /// Decodes a packet from the uncompressed contents of a frame, like
+            /// `packet::decode_packet` does for protocol 47.
+            pub fn decode_packet(&self, direction: Direction, state: State, frame: &[u8]) -> Result<PacketEnum> {
+                match *self {
+                    $(Version::$version => $vmod::decode_packet(direction, state, frame),)*
+                    Version::V47 => packet::decode_packet(direction, state, frame)
+                }
+            }
+
+            /// Reads a new packet from a reader, like `packet::read_packet` does for protocol 47.
+            pub fn read_packet(&self, direction: Direction, state: State, compression: Option<usize>, src: &mut Read) -> Result<PacketEnum> {
+                let frame = try!(packet::read_frame(src, compression));
+                self.decode_packet(direction, state, &frame)
+            }
+
+            /// Writes a full packet to a writer with the ID and layout used by this version.
+            pub fn write_packet<P: Packet + Any>(&self, packet: &P, dst: &mut Write, compression: Option<usize>) -> Result<()> {
+                let written = match *self {
+                    $(Version::$version => try!($vmod::write_packet(packet, dst, compression)),)*
+                    Version::V47 => {
+                        try!(packet.write(dst, compression));
+                        true
+                    }
+                };
+                if written {
+                    Ok(())
+                } else {
+                    Err(Error::invalid("packet not supported by protocol version", Some(format!("{:?} {:?} packet with id {:#x} has no equivalent in {:?}", packet.state(), packet.direction(), packet.id(), self))))
+                }
+            }
+        }
+
+        $(
+            mod $vmod {
+                #![allow(unused_imports)]
+                use error::{Error, ErrorKind, Result};
+                use packet::{Direction, Packet, PacketEnum, Protocol, State, decode_body_with, decode_field, write_frame};
+                use packet::{handshake, login, play, status};
+                use types::consts::*;
+                use types::{Arr, BlockPos, Chat, Var};
+                use super::{CapeSkinParts, EyePosition, Omitted, OnGroundFlags, StancePosition};
+
+                use std::any::Any;
+                use std::io::prelude::*;
+
+                pub fn decode_packet(direction: Direction, state: State, frame: &[u8]) -> Result<PacketEnum> {
+                    let mut src = frame;
+                    let id = try!(<Var<i32> as Protocol>::proto_decode(&mut src));
+                    match state {
+                        $(State::$state => match direction {
+                            Direction::Clientbound => match id {
+                                $($c_id => translate_decode!($state_mod, clientbound, $c_name, src, { $($c_fields)* }).map(|packet| PacketEnum::$state($state_mod::PacketEnum::Clientbound($state_mod::clientbound::PacketEnum::$c_name(packet)))),)*
+                                _ => Err(Error::new(ErrorKind::UnknownPacketId { state: state, direction: direction, id: id }))
+                            },
+                            Direction::Serverbound => match id {
+                                $($s_id => translate_decode!($state_mod, serverbound, $s_name, src, { $($s_fields)* }).map(|packet| PacketEnum::$state($state_mod::PacketEnum::Serverbound($state_mod::serverbound::PacketEnum::$s_name(packet)))),)*
+                                _ => Err(Error::new(ErrorKind::UnknownPacketId { state: state, direction: direction, id: id }))
+                            }
+                        }),*
+                    }
+                }
+
+                /// Writes `packet` if this version has an equivalent for it, returning whether it did.
+                pub fn write_packet(packet: &Any, dst: &mut Write, compression: Option<usize>) -> Result<bool> {
+                    $(
+                        $(
+                            if let Some(p) = packet.downcast_ref::<$state_mod::clientbound::$c_name>() {
+                                let mut body = Vec::new();
+                                try!(translate_encode!($c_name, p, body, { $($c_fields)* }));
+                                try!(write_frame($c_id, &body, dst, compression));
+                                return Ok(true);
+                            }
+                        )*
+                        $(
+                            if let Some(p) = packet.downcast_ref::<$state_mod::serverbound::$s_name>() {
+                                let mut body = Vec::new();
+                                try!(translate_encode!($s_name, p, body, { $($s_fields)* }));
+                                try!(write_frame($s_id, &body, dst, compression));
+                                return Ok(true);
+                            }
+                        )*
+                    )*
+                    Ok(false)
+                }
+            }
+        )*
+    }
+}
+
+versions! {
+    // 1.7.6 to 1.7.10. This table is partial: Play only translates the packets a client needs
+    // to log in, move around and stay connected, since most of its other packets changed
+    // layout in 1.8. In particular, chunks aren't translated, so the client doesn't receive
+    // any terrain. Reading the other packets fails with `ErrorKind::UnknownPacketId`, which
+    // callers have to skip.
+    V5 (5) => v5 {
+        Handshaking => handshake {
+            clientbound {}
+            serverbound {
+                0x00 => Handshake {}
+            }
+        }
+        Play => play {
+            clientbound {
+                0x00 => KeepAlive { keep_alive_id: i32 }
+                0x01 => JoinGame { entity_id: i32, gamemode: u8, dimension: Dimension, difficulty: u8, max_players: u8, level_type: String, reduced_debug_info: Omitted<bool> }
+                0x03 => TimeUpdate {}
+                0x05 => WorldSpawn { location: [i32; 3] }
+                0x08 => PlayerPositionAndLook { position: EyePosition, yaw: f32, pitch: f32, flags: OnGroundFlags }
+                0x09 => HeldItemChange {}
+                0x2e => CloseWindow {}
+                0x3f => PluginMessage { channel: String, data: Arr<i16, u8> }
+                0x40 => Disconnect {}
+            }
+            serverbound {
+                0x00 => KeepAlive { keep_alive_id: i32 }
+                0x01 => ChatMessage {}
+                0x03 => PlayerIdle {}
+                0x04 => PlayerPosition { position: StancePosition, on_ground: bool }
+                0x05 => PlayerLook {}
+                0x06 => PlayerPositionAndLook { position: StancePosition, yaw: f32, pitch: f32, on_ground: bool }
+                0x09 => HeldItemChange {}
+                0x0d => CloseWindow {}
+                0x15 => ClientSettings { locale: String, view_distance: i8, chat_mode: i8, chat_colors: bool, displayed_skin_parts: CapeSkinParts }
+                0x17 => PluginMessage { channel: String, data: Arr<i16, u8> }
+            }
+        }
+        Status => status {
+            clientbound {
+                0x00 => StatusResponse {}
+                0x01 => Pong {}
+            }
+            serverbound {
+                0x00 => StatusRequest {}
+                0x01 => Ping {}
+            }
+        }
+        Login => login {
+            clientbound {
+                0x00 => Disconnect {}
+                0x01 => EncryptionRequest { server_id: String, pubkey: Arr<i16, u8>, verify_token: Arr<i16, u8> }
+                0x02 => LoginSuccess {}
+            }
+            serverbound {
+                0x00 => LoginStart {}
+                0x01 => EncryptionResponse { shared_secret: Arr<i16, u8>, verify_token: Arr<i16, u8> }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use packet::{Direction, Packet, PacketEnum, State};
+    use packet::login;
+    use packet::login::serverbound::EncryptionResponse;
+    use packet::play;
+    use packet::play::clientbound::{JoinGame, KeepAlive, PlayerPositionAndLook, ServerDifficulty};
+    use types::consts::Dimension;
+    use version::Version;
+
+    #[test]
+    fn from_protocol() {
+        assert_eq!(Version::from_protocol(5), Some(Version::V5));
+        assert_eq!(Version::from_protocol(47), Some(Version::V47));
+        assert_eq!(Version::from_protocol(4), None);
+        assert_eq!(Version::V5.protocol(), 5);
+    }
+
+    #[test]
+    fn v5_encryption_response() {
+        let packet = EncryptionResponse { shared_secret: vec![1, 2], verify_token: vec![3] };
+        let mut dst = Vec::new();
+        Version::V5.write_packet(&packet, &mut dst, None).unwrap();
+        assert_eq!(&dst, &vec![8, 0x01, 0, 2, 1, 2, 0, 1, 3]);
+
+        match Version::V5.decode_packet(Direction::Serverbound, State::Login, &dst[1..]).unwrap() {
+            PacketEnum::Login(login::PacketEnum::Serverbound(login::serverbound::PacketEnum::EncryptionResponse(p))) => {
+                assert_eq!(p.shared_secret, vec![1, 2]);
+                assert_eq!(p.verify_token, vec![3]);
+            }
+            _ => panic!("decoded the wrong packet")
+        }
+    }
+
+    #[test]
+    fn v5_keep_alive() {
+        let mut dst = Vec::new();
+        Version::V5.write_packet(&KeepAlive { keep_alive_id: 1 }, &mut dst, None).unwrap();
+        assert_eq!(&dst, &vec![5, 0x00, 0, 0, 0, 1]);
+        assert!(Version::V5.decode_packet(Direction::Clientbound, State::Play, &dst[1..]).is_ok());
+        // The 1.8 layout has a VarInt instead, so the body is too short for protocol 5.
+        assert!(Version::V5.decode_packet(Direction::Clientbound, State::Play, &[0x00, 0x01]).is_err());
+    }
+
+    #[test]
+    fn v5_join_game() {
+        let packet = JoinGame {
+            entity_id: 1,
+            gamemode: 0,
+            dimension: Dimension::Overworld,
+            difficulty: 0,
+            max_players: 20,
+            level_type: "default".to_string(),
+            reduced_debug_info: true
+        };
+        let mut dst = Vec::new();
+        Version::V5.write_packet(&packet, &mut dst, None).unwrap();
+        // Protocol 5 has no reduced_debug_info at the end.
+        assert_eq!(&dst, &vec![17, 0x01, 0, 0, 0, 1, 0, 0, 0, 20, 7, 0x64, 0x65, 0x66, 0x61, 0x75, 0x6c, 0x74]);
+        match Version::V5.decode_packet(Direction::Clientbound, State::Play, &dst[1..]).unwrap() {
+            PacketEnum::Play(play::PacketEnum::Clientbound(play::clientbound::PacketEnum::JoinGame(p))) => {
+                assert_eq!(p.level_type, "default");
+                assert!(!p.reduced_debug_info);
+            }
+            _ => panic!("decoded the wrong packet")
+        }
+    }
+
+    #[test]
+    fn v5_position_and_look() {
+        let packet = PlayerPositionAndLook { position: [0.5, 64.0, 0.5], yaw: 0.0, pitch: 0.0, flags: 0 };
+        let mut dst = Vec::new();
+        Version::V5.write_packet(&packet, &mut dst, None).unwrap();
+        assert_eq!(dst.len(), 2 + 24 + 8 + 1);
+        match Version::V5.decode_packet(Direction::Clientbound, State::Play, &dst[1..]).unwrap() {
+            PacketEnum::Play(play::PacketEnum::Clientbound(play::clientbound::PacketEnum::PlayerPositionAndLook(p))) => {
+                assert_eq!(p.position, [0.5, 64.0, 0.5]);
+                assert_eq!(p.flags, 0);
+            }
+            _ => panic!("decoded the wrong packet")
+        }
+        // Relative positions can't be sent to protocol 5.
+        let mut dst = Vec::new();
+        let err = Version::V5.write_packet(&PlayerPositionAndLook { flags: 0x01, ..packet }, &mut dst, None).unwrap_err();
+        assert_eq!(err.location(), "PlayerPositionAndLook.flags");
+        assert!(dst.is_empty());
+    }
+
+    #[test]
+    fn v5_serverbound_position() {
+        let packet = play::serverbound::PlayerPosition { position: [1.0, 70.0, -1.0], on_ground: true };
+        let mut dst = Vec::new();
+        Version::V5.write_packet(&packet, &mut dst, None).unwrap();
+        // The stance follows the height of the feet.
+        assert_eq!(dst.len(), 2 + 32 + 1);
+        assert_eq!(&dst[18..26], &[0x40, 0x51, 0xe7, 0xae, 0x14, 0x7a, 0xe1, 0x48]);
+        match Version::V5.decode_packet(Direction::Serverbound, State::Play, &dst[1..]).unwrap() {
+            PacketEnum::Play(play::PacketEnum::Serverbound(play::serverbound::PacketEnum::PlayerPosition(p))) => {
+                assert_eq!(p.position, [1.0, 70.0, -1.0]);
+                assert!(p.on_ground);
+            }
+            _ => panic!("decoded the wrong packet")
+        }
+    }
+
+    #[test]
+    fn v5_client_settings() {
+        let frame = [0x15, 5, 0x65, 0x6e, 0x5f, 0x55, 0x53, 8, 0, 1, 2, 0];
+        match Version::V5.decode_packet(Direction::Serverbound, State::Play, &frame).unwrap() {
+            PacketEnum::Play(play::PacketEnum::Serverbound(play::serverbound::PacketEnum::ClientSettings(p))) => {
+                assert_eq!(p.locale, "en_US");
+                assert_eq!(p.view_distance, 8);
+                // Everything but the cape.
+                assert_eq!(p.displayed_skin_parts, 0x7e);
+                let mut dst = Vec::new();
+                Version::V5.write_packet(&p, &mut dst, None).unwrap();
+                // The difficulty is sent as peaceful.
+                assert_eq!(&dst[1..11], &frame[..10]);
+                assert_eq!(&dst[11..], &[0, 0]);
+            }
+            _ => panic!("decoded the wrong packet")
+        }
+    }
+
+    #[test]
+    fn v5_plugin_message() {
+        let packet = play::serverbound::PluginMessage { channel: "MC|Brand".to_string(), data: vec![7] };
+        let mut dst = Vec::new();
+        Version::V5.write_packet(&packet, &mut dst, None).unwrap();
+        // The data has a short length prefix in protocol 5.
+        assert_eq!(&dst[11..], &[0, 1, 7]);
+        match Version::V5.decode_packet(Direction::Serverbound, State::Play, &dst[1..]).unwrap() {
+            PacketEnum::Play(play::PacketEnum::Serverbound(play::serverbound::PacketEnum::PluginMessage(p))) => {
+                assert_eq!(p.channel, "MC|Brand");
+                assert_eq!(p.data, vec![7]);
+            }
+            _ => panic!("decoded the wrong packet")
+        }
+    }
+
+    #[test]
+    fn v5_unsupported_packet() {
+        let packet = ServerDifficulty { difficulty: 0 };
+        let mut dst = Vec::new();
+        assert!(Version::V5.write_packet(&packet, &mut dst, None).is_err());
+        assert!(dst.is_empty());
+        assert!(Version::V47.write_packet(&packet, &mut dst, None).is_ok());
+    }
+
+    #[test]
+    fn v47_read_packet() {
+        let mut src = Vec::new();
+        KeepAlive { keep_alive_id: 1 }.write(&mut src, None).unwrap();
+        let mut src = io::Cursor::new(src);
+        assert!(Version::V47.read_packet(Direction::Clientbound, State::Play, None, &mut src).is_ok());
+    }
+}